@@ -1,4 +1,14 @@
-use clap::Parser;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand};
+use serde_json::Value;
+
+use gemini_unlock::hooks::PackageManager;
+use gemini_unlock::locale::{normalize_locale, DEFAULT_REGION};
+use gemini_unlock::policy::{validate_policy, PolicyLevel};
+use gemini_unlock::process::ChromeChannel;
+use gemini_unlock::shutdown::{parse_timeout_ms, ShutdownPolicy};
 
 /// Command line arguments definition.
 ///
@@ -12,7 +22,7 @@ use clap::Parser;
     name = "chrome_gemini",
     version,
     about = "Enable Chrome Gemini features by modifying Local State configuration",
-    after_help = "Examples:\n  chrome_gemini              # Apply patches (requires Chrome to be closed)\n  chrome_gemini -k            # Close Chrome and apply patches\n  chrome_gemini -r            # Restore from backup\n\nEnvironment Variables:\n  RUST_LOG=info              # Enable info level logging\n  RUST_LOG=debug             # Enable debug level logging"
+    after_help = "Examples:\n  chrome_gemini              # Apply patches (requires Chrome to be closed)\n  chrome_gemini -k            # Close Chrome and apply patches\n  chrome_gemini -k --relaunch # Close Chrome, apply patches and start it again\n  chrome_gemini -r            # Restore from backup\n  chrome_gemini --dry-run     # Show the changes without writing them\n  chrome_gemini --locale      # Also switch Chrome's UI language to en-US\n  chrome_gemini -k --shutdown term:30s,kill:5s  # Give Chrome 30s to exit on TERM\n  chrome_gemini --wait 10m    # Patch as soon as Chrome is closed, give up after 10 minutes\n  chrome_gemini profiles      # List Chrome profiles\n  chrome_gemini doctor --fix  # Check prerequisites and repair what can be repaired\n  chrome_gemini verify-macs   # Report protected preferences Chrome would reset\n  sudo chrome_gemini policy install  # Allow Gemini through a recommended policy file\n  chrome_gemini launcher      # Start Chrome with --variations-override-country from the menu\n  chrome_gemini flags-conf    # Add the flags to ~/.config/chrome-flags.conf\n  chrome_gemini flags enable glic  # Enable chrome://flags/#glic\n  sudo chrome_gemini initial-prefs --skip-first-run  # Provision a fresh install\n  chrome_gemini --locale watch  # Keep the patch and locale applied while Chrome reverts them\n  chrome_gemini --profile Default install-service  # Re-apply automatically whenever Chrome reverts the patch\n  sudo chrome_gemini hook install  # Re-patch after Chrome updates through apt, dnf or pacman\n  chrome_gemini reapply       # Patch again after a Chrome update, with the last run's options\n  chrome_gemini status        # Show what is patched and which flags files carry the block\n  chrome_gemini discover-fields --suggest  # Find moved eligibility fields and suggest rules\n  chrome_gemini uninstall     # Remove installed policy, launcher and flags files again\n\nEnvironment Variables:\n  RUST_LOG=info              # Enable info level logging\n  RUST_LOG=debug             # Enable debug level logging"
 )]
 pub struct Cli {
     /// Close running Chrome before applying patches [short aliases: -k]
@@ -22,4 +32,308 @@ pub struct Cli {
     /// Restore Local State from backup instead of applying patches [short aliases: -r]
     #[arg(long, short = 'r', default_value_t = false)]
     pub restore: bool,
+
+    /// Show what would change without writing any file or stopping Chrome
+    #[arg(long, default_value_t = false, conflicts_with_all = ["kill_chrome", "wait"])]
+    pub dry_run: bool,
+
+    /// Remove a stale SingletonLock left behind by a crashed or killed Chrome
+    #[arg(long, default_value_t = false)]
+    pub remove_stale_lock: bool,
+
+    /// Start Chrome again with its original command line after --kill-chrome
+    #[arg(long, default_value_t = false, requires = "kill_chrome")]
+    pub relaunch: bool,
+
+    /// Wait until Chrome is closed normally, then patch immediately; optional timeout such as "10m", "90s" or "500ms"
+    #[arg(
+        long,
+        value_name = "TIMEOUT",
+        num_args = 0..=1,
+        conflicts_with = "kill_chrome",
+        value_parser = parse_duration
+    )]
+    pub wait: Option<Option<Duration>>,
+
+    /// Switch Chrome's UI language, e.g. "en-US" [default: the region preset's locale]
+    #[arg(
+        long,
+        value_name = "TAG",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_REGION.locale,
+        value_parser = parse_locale
+    )]
+    pub locale: Option<String>,
+
+    /// With --locale, also set each profile's preferred web content languages
+    #[arg(long, default_value_t = false, requires = "locale")]
+    pub accept_languages: bool,
+
+    /// Shutdown stages used by --kill-chrome, e.g. "close:10s,term:10s,kill:5s"
+    #[arg(long, value_name = "STAGES")]
+    pub shutdown: Option<ShutdownPolicy>,
+
+    /// Config file [default: ~/.config/gemini-unlock/config.json]
+    #[arg(long, value_name = "PATH", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Limit profile-level operations to one profile, by directory or display name
+    #[arg(long, value_name = "NAME|DIR", global = true)]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+/// Subcommands; without one, patches are applied.
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// List Chrome profiles from Local State
+    Profiles,
+    /// Check every prerequisite for Gemini and explain how to fix what is missing
+    Doctor {
+        /// Apply automatic fixes where possible (Chrome must be closed)
+        #[arg(long, default_value_t = false)]
+        fix: bool,
+    },
+    /// Check the MACs of protected preferences and report the ones Chrome would reset
+    VerifyMacs {
+        /// Hex-encoded MAC seed [default: from config, empty for Chromium and Linux]
+        #[arg(long, value_name = "HEX")]
+        seed: Option<String>,
+
+        /// Device ID mixed into the MACs [default: from config, empty on Linux]
+        #[arg(long, value_name = "ID")]
+        device_id: Option<String>,
+    },
+    /// Write or remove a local Chrome policy file that allows Gemini (Linux)
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
+    /// Create a .desktop override and shell wrapper that start Chrome with the variations country and features (Linux)
+    Launcher {
+        /// Chrome channel: stable, beta, dev, canary or chromium
+        #[arg(long, value_name = "CHANNEL", default_value = "stable")]
+        channel: ChromeChannel,
+
+        /// Variations country [default: from config, "us"]
+        #[arg(long, value_name = "CODE")]
+        country: Option<String>,
+
+        /// Features to enable, comma-separated [default: from config]
+        #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+        features: Option<Vec<String>>,
+
+        /// Remove the launcher files of the channel instead
+        #[arg(long, default_value_t = false, conflicts_with_all = ["country", "features"])]
+        uninstall: bool,
+    },
+    /// Add, update or remove the tool's block in ~/.config/chrome-flags.conf, read by distro wrapper scripts
+    FlagsConf {
+        /// Chrome channel: stable, beta, dev, canary or chromium
+        #[arg(long, value_name = "CHANNEL", default_value = "stable")]
+        channel: ChromeChannel,
+
+        /// Variations country [default: from config, "us"]
+        #[arg(long, value_name = "CODE")]
+        country: Option<String>,
+
+        /// Features to enable, comma-separated [default: from config]
+        #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+        features: Option<Vec<String>>,
+
+        /// Remove the block instead
+        #[arg(long, default_value_t = false, conflicts_with_all = ["country", "features"])]
+        remove: bool,
+    },
+    /// List, enable, disable or reset Gemini-related chrome://flags entries
+    Flags {
+        #[command(subcommand)]
+        action: FlagsAction,
+    },
+    /// Write Chrome's initial_preferences so a fresh install starts with the Gemini settings
+    InitialPrefs {
+        /// File to write [default: from config, or next to the Chrome install]
+        #[arg(long, value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Locale for the content languages [default: the region preset's locale]
+        #[arg(long, value_name = "TAG", value_parser = parse_locale)]
+        locale: Option<String>,
+
+        /// Also skip the first-run UI and the default-browser prompt
+        #[arg(long, default_value_t = false)]
+        skip_first_run: bool,
+    },
+    /// Watch Local State in the foreground and re-apply the patch whenever Chrome reverts it
+    Watch {
+        /// How often to check Local State
+        #[arg(long, value_name = "DURATION", default_value = "2s", value_parser = parse_duration)]
+        interval: Duration,
+
+        /// How long Local State must stay unchanged, with Chrome closed, before it is checked
+        #[arg(long, value_name = "DURATION", default_value = "3s", value_parser = parse_duration)]
+        settle: Duration,
+
+        /// Check once after Local State has settled and exit, as the systemd service does
+        #[arg(long, default_value_t = false)]
+        once: bool,
+    },
+    /// Install and enable a systemd user unit that re-applies the patch when Chrome reverts it (Linux)
+    InstallService {
+        /// Run on a timer with this interval instead of on every change of Local State, e.g. "15m"
+        #[arg(long, value_name = "INTERVAL", value_parser = parse_duration)]
+        timer: Option<Duration>,
+
+        #[command(flatten)]
+        target: UnitTarget,
+    },
+    /// Disable and remove the units written by install-service
+    UninstallService {
+        #[command(flatten)]
+        target: UnitTarget,
+    },
+    /// Install or remove a package-manager hook that re-applies the patch after Chrome updates (Linux)
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
+    /// Apply the patch again with the options recorded by the last run, e.g. after a Chrome update
+    Reapply,
+    /// Search the whole Local State tree for Gemini-related keys, e.g. after a field moved
+    DiscoverFields {
+        /// JSON file to search [default: Local State]
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        /// Key pattern to search for instead of the built-in list, "*" matching any text; repeatable
+        #[arg(long = "pattern", value_name = "PATTERN")]
+        patterns: Vec<String>,
+
+        /// Also print a patch rule for each field whose target value can be derived
+        #[arg(long, default_value_t = false)]
+        suggest: bool,
+    },
+    /// Show the patch state of Local State and the tool's blocks in flags files
+    Status,
+    /// List the files installed outside the Chrome profile and whether they changed
+    Audit,
+    /// Remove every file installed outside the Chrome profile
+    Uninstall {
+        /// Also remove files that were changed since they were installed
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+}
+
+/// `policy` actions.
+#[derive(Debug, Subcommand)]
+pub enum PolicyAction {
+    /// Write the policy file, validated against the embedded policy schema
+    Install {
+        #[command(flatten)]
+        target: PolicyTarget,
+
+        /// Override or add a policy, e.g. "GeminiActOnWebSettings=1" (repeatable)
+        #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_policy_setting)]
+        set: Vec<(String, Value)>,
+    },
+    /// Remove the policy file
+    Remove {
+        #[command(flatten)]
+        target: PolicyTarget,
+    },
+    /// List the policies that can be written and their accepted values
+    Schema,
+}
+
+/// `flags` actions; changes need Chrome to be closed, as patches do (`-k`, `--wait`).
+#[derive(Debug, Subcommand)]
+pub enum FlagsAction {
+    /// List the catalog flags and their current option
+    List,
+    /// Enable a flag
+    Enable {
+        /// Flag name, e.g. "glic"
+        name: String,
+
+        /// Option index from `flags list`, for flags with several enabled variants
+        #[arg(long, value_name = "INDEX", default_value_t = 1)]
+        variant: usize,
+    },
+    /// Disable a flag
+    Disable {
+        /// Flag name, e.g. "glic"
+        name: String,
+    },
+    /// Remove a flag's entry so Chrome uses its default
+    Reset {
+        /// Flag name, e.g. "glic"
+        name: String,
+    },
+}
+
+/// `hook` actions; the hook root comes from the config [default: `/`].
+#[derive(Debug, Subcommand)]
+pub enum HookAction {
+    /// Write the hook and opt users in
+    Install {
+        /// apt, dnf5, dnf (dnf4) or pacman [default: detected]
+        #[arg(long, value_name = "MANAGER")]
+        manager: Option<PackageManager>,
+
+        /// User whose Chrome the hook re-patches (repeatable) [default: the user running sudo]
+        #[arg(long = "user", value_name = "NAME")]
+        users: Vec<String>,
+    },
+    /// Opt users out, or without --user remove the hooks and the opt-in list
+    Remove {
+        /// User to opt out (repeatable)
+        #[arg(long = "user", value_name = "NAME")]
+        users: Vec<String>,
+    },
+    /// Re-apply the patch for every opted-in user; run by the hook
+    Run,
+}
+
+/// Which policy file a `policy` action works on.
+#[derive(Debug, Args)]
+pub struct PolicyTarget {
+    /// "recommended" (users can change it) or "managed" (enforced)
+    #[arg(long, value_name = "LEVEL", default_value = "recommended")]
+    pub level: PolicyLevel,
+
+    /// Use Chromium's policy directory instead of Chrome's
+    #[arg(long, default_value_t = false)]
+    pub chromium: bool,
+}
+
+/// Where the `install-service` units live.
+#[derive(Debug, Args)]
+pub struct UnitTarget {
+    /// Unit directory [default: ~/.config/systemd/user]; systemctl is not run when it is given
+    #[arg(long, value_name = "DIR")]
+    pub unit_dir: Option<PathBuf>,
+}
+
+/// Parse and normalize a `--locale` tag.
+fn parse_locale(value: &str) -> Result<String, String> {
+    normalize_locale(value).map_err(|e| e.to_string())
+}
+
+/// Parse and validate a `policy install --set` value such as `GeminiSettings=0`.
+fn parse_policy_setting(value: &str) -> Result<(String, Value), String> {
+    let (name, raw) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got {value}"))?;
+    let name = name.trim().to_string();
+    let parsed = serde_json::from_str(raw.trim()).unwrap_or_else(|_| Value::from(raw.trim()));
+    validate_policy(&name, &parsed).map_err(|e| e.to_string())?;
+    Ok((name, parsed))
+}
+
+/// Parse a duration such as `90s` or `10m`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    parse_timeout_ms(value).map(Duration::from_millis)
 }
@@ -0,0 +1,299 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::{Value, json};
+
+use crate::locale::DEFAULT_REGION;
+use crate::patcher::{PatchRule, local_state_rules};
+
+/// First Chrome major version that ships Gemini in Chrome.
+pub const MIN_GEMINI_MAJOR: u32 = 137;
+
+/// Local State field whose shape differs between rule sets.
+pub const CONSISTENCY_POINTER: &str = "/variations_permanent_consistency_country";
+
+/// A Chrome version such as `138.0.7204.92`; missing trailing numbers are 0.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::compat::ChromeVersion;
+///
+/// let version: ChromeVersion = "138.0.7204.92".parse().unwrap();
+/// assert_eq!(version.major(), 138);
+/// assert!(version < "140.0.7339.80".parse().unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct ChromeVersion {
+    /// Major, minor, build and patch number.
+    pub parts: [u32; 4],
+}
+
+impl ChromeVersion {
+    /// Major version, which is what the compatibility table is keyed on.
+    pub fn major(&self) -> u32 {
+        self.parts[0]
+    }
+}
+
+impl fmt::Display for ChromeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [major, minor, build, patch] = self.parts;
+        write!(f, "{major}.{minor}.{build}.{patch}")
+    }
+}
+
+impl FromStr for ChromeVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = [0; 4];
+        for (index, number) in s.trim().split('.').enumerate() {
+            let part = parts
+                .get_mut(index)
+                .ok_or_else(|| format!("invalid Chrome version (more than 4 numbers): {s}"))?;
+            *part = number
+                .parse()
+                .map_err(|_| format!("invalid Chrome version: {s}"))?;
+        }
+        Ok(Self { parts })
+    }
+}
+
+/// Shape of `variations_permanent_consistency_country`.
+///
+/// # Examples
+///
+/// ```text
+/// ConsistencyShape::VersionAndCountry // ["140.0.7339.80", "us"]
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConsistencyShape {
+    /// `["<chrome version>", "us"]`; Chrome re-evaluates the country when
+    /// the version no longer matches.
+    VersionAndCountry,
+}
+
+/// The Local State edits for a range of Chrome releases.
+///
+/// # Examples
+///
+/// ```text
+/// RuleSet { id: 2, first_major: 140, last_major: 146, consistency: ConsistencyShape::VersionAndCountry }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RuleSet {
+    /// Rule set version, recorded when patching.
+    pub id: u32,
+    /// First major version the rule set was tested with.
+    pub first_major: u32,
+    /// Last major version the rule set was tested with.
+    pub last_major: u32,
+    /// Shape of the consistency-country array.
+    pub consistency: ConsistencyShape,
+}
+
+/// Embedded compatibility table, oldest releases first.
+///
+/// Rule set 1 wrote a bare `["us"]`, which Chrome does not store; it was
+/// withdrawn and its id is not reused, so patches recorded with it are
+/// reported as made by a different rule set.
+pub const RULE_SETS: &[RuleSet] = &[
+    RuleSet {
+        id: 2,
+        first_major: MIN_GEMINI_MAJOR,
+        last_major: 146,
+        consistency: ConsistencyShape::VersionAndCountry,
+    },
+];
+
+impl RuleSet {
+    /// Newest rule set in the table.
+    pub fn latest() -> &'static Self {
+        RULE_SETS.last().expect("compatibility table is not empty")
+    }
+
+    /// Consistency-country value for `version`; `None` if the shape needs
+    /// the version and it is unknown, so the field is left alone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gemini_unlock::compat::RuleSet;
+    /// use serde_json::json;
+    ///
+    /// let version = "140.0.7339.80".parse().unwrap();
+    /// assert_eq!(RuleSet::latest().consistency_value(Some(&version)), Some(json!(["140.0.7339.80", "us"])));
+    /// assert_eq!(RuleSet::latest().consistency_value(None), None);
+    /// ```
+    pub fn consistency_value(&self, version: Option<&ChromeVersion>) -> Option<Value> {
+        match self.consistency {
+            ConsistencyShape::VersionAndCountry => {
+                version.map(|version| json!([version.to_string(), DEFAULT_REGION.country]))
+            }
+        }
+    }
+
+    /// The Local State rules of this rule set for `version`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gemini_unlock::compat::{RuleSet, CONSISTENCY_POINTER};
+    ///
+    /// let rules = RuleSet::latest().local_state_rules(None);
+    /// assert!(rules.iter().all(|rule| rule.pointer != CONSISTENCY_POINTER));
+    /// ```
+    pub fn local_state_rules(&self, version: Option<&ChromeVersion>) -> Vec<PatchRule> {
+        let consistency = self.consistency_value(version);
+        local_state_rules()
+            .into_iter()
+            .filter_map(|rule| {
+                if rule.pointer != CONSISTENCY_POINTER {
+                    return Some(rule);
+                }
+                consistency.clone().map(|value| PatchRule { value, ..rule })
+            })
+            .collect()
+    }
+}
+
+/// How a Chrome version relates to the compatibility table.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::compat::{compatibility, Compatibility};
+///
+/// let version = "138.0.7204.92".parse().unwrap();
+/// assert!(matches!(compatibility(Some(&version)), Compatibility::Tested(set) if set.id == 2));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compatibility {
+    /// Inside the range of a rule set.
+    Tested(&'static RuleSet),
+    /// Newer than every tested release; the newest rule set is used.
+    Untested(&'static RuleSet),
+    /// Older than the first release with Gemini.
+    Unsupported,
+    /// The version could not be determined; the newest rule set is used.
+    Unknown(&'static RuleSet),
+}
+
+impl Compatibility {
+    /// Rule set to patch with, `None` for unsupported versions.
+    pub fn rule_set(&self) -> Option<&'static RuleSet> {
+        match self {
+            Self::Tested(set) | Self::Untested(set) | Self::Unknown(set) => Some(set),
+            Self::Unsupported => None,
+        }
+    }
+}
+
+/// Look `version` up in [`RULE_SETS`].
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::compat::{compatibility, Compatibility};
+///
+/// let version = "120.0.6099.71".parse().unwrap();
+/// assert_eq!(compatibility(Some(&version)), Compatibility::Unsupported);
+/// ```
+pub fn compatibility(version: Option<&ChromeVersion>) -> Compatibility {
+    let Some(version) = version else {
+        return Compatibility::Unknown(RuleSet::latest());
+    };
+    let major = version.major();
+    if major < MIN_GEMINI_MAJOR {
+        return Compatibility::Unsupported;
+    }
+    RULE_SETS
+        .iter()
+        .find(|set| (set.first_major..=set.last_major).contains(&major))
+        .map_or(Compatibility::Untested(RuleSet::latest()), Compatibility::Tested)
+}
+
+/// First version number in `chrome --version` output.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::compat::parse_version_output;
+///
+/// let version = parse_version_output("Chromium 140.0.7339.80 built on Debian").unwrap();
+/// assert_eq!(version.to_string(), "140.0.7339.80");
+/// ```
+pub fn parse_version_output(output: &str) -> Option<ChromeVersion> {
+    output
+        .split_whitespace()
+        .filter(|word| word.contains('.'))
+        .find_map(|word| word.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> ChromeVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn table_is_contiguous() {
+        assert_eq!(RULE_SETS[0].first_major, MIN_GEMINI_MAJOR);
+        for pair in RULE_SETS.windows(2) {
+            assert_eq!(pair[0].last_major + 1, pair[1].first_major);
+            assert_eq!(pair[0].id + 1, pair[1].id);
+        }
+    }
+
+    #[test]
+    fn picks_rule_set_by_major() {
+        assert_eq!(
+            compatibility(Some(&version("137.0.7151.55"))).rule_set().unwrap().id,
+            2
+        );
+        assert_eq!(
+            compatibility(Some(&version("140.0.7339.80"))).rule_set().unwrap().id,
+            2
+        );
+        assert!(matches!(
+            compatibility(Some(&version("180.0.1.2"))),
+            Compatibility::Untested(set) if set.id == RuleSet::latest().id
+        ));
+        assert_eq!(compatibility(Some(&version("136.0.1.2"))).rule_set(), None);
+    }
+
+    #[test]
+    fn rejects_malformed_versions() {
+        for input in ["", "138.", "138.0.7204.92.1", "138.0.x.92", "v138"] {
+            assert!(input.parse::<ChromeVersion>().is_err(), "{input}");
+        }
+        assert_eq!(version("138.0.7204").to_string(), "138.0.7204.0");
+    }
+
+    #[test]
+    fn consistency_keeps_chrome_shape() {
+        // Shape of the field in a Chrome 138 Local State
+        let local_state = json!({
+            "variations_country": "de",
+            "variations_permanent_consistency_country": ["138.0.7204.92", "de"]
+        });
+        let rules = compatibility(Some(&version("138.0.7204.92")))
+            .rule_set()
+            .unwrap()
+            .local_state_rules(Some(&version("138.0.7204.92")));
+        let rule = rules
+            .iter()
+            .find(|rule| rule.pointer == CONSISTENCY_POINTER)
+            .unwrap();
+        assert_eq!(rule.value, json!(["138.0.7204.92", "us"]));
+        let current = local_state.pointer(CONSISTENCY_POINTER).unwrap();
+        assert_eq!(
+            current.as_array().unwrap().len(),
+            rule.value.as_array().unwrap().len()
+        );
+        assert!(rules.iter().all(|rule| !rule.create));
+    }
+}
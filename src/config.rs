@@ -0,0 +1,239 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::locale::DEFAULT_REGION;
+use crate::prefhash::PrefHasher;
+use crate::shutdown::ShutdownPolicy;
+
+/// Config file name inside the tool's config directory.
+pub const CONFIG_FILE: &str = "config.json";
+
+/// Tool configuration loaded from `config.json`.
+///
+/// Every field is optional; missing fields take their defaults and command
+/// line options override file values.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::config::Config;
+///
+/// let config: Config = serde_json::from_str(r#"{"shutdown":{"poll_interval_ms":100}}"#).unwrap();
+/// assert_eq!(config.shutdown.poll_interval_ms, 100);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// How `--kill-chrome` stops Chrome.
+    pub shutdown: ShutdownPolicy,
+    /// Inputs of Chrome's preference MACs.
+    pub pref_hash: PrefHashConfig,
+    /// Root that the Linux policy directories are under [default: `/`].
+    pub policy_root: Option<PathBuf>,
+    /// Command-line flags used by `launcher`.
+    pub launcher: LaunchFlagsConfig,
+    /// Where `initial-prefs` writes [default: next to the Chrome install].
+    pub initial_preferences: Option<PathBuf>,
+    /// Root that package-manager hooks are installed under [default: `/`].
+    pub hook_root: Option<PathBuf>,
+}
+
+impl Config {
+    /// Root of the Linux policy directories.
+    pub fn policy_root(&self) -> PathBuf {
+        self.policy_root.clone().unwrap_or_else(|| PathBuf::from("/"))
+    }
+
+    /// Root of the package-manager hook directories.
+    pub fn hook_root(&self) -> PathBuf {
+        self.hook_root.clone().unwrap_or_else(|| PathBuf::from("/"))
+    }
+}
+
+/// Seed and device ID used to compute preference MACs.
+///
+/// Both default to empty.
+///
+/// # Examples
+///
+/// ```text
+/// {"pref_hash": {"seed": "e748f336...", "device_id": "S-1-5-21-..."}}
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrefHashConfig {
+    /// Hex-encoded HMAC seed.
+    pub seed: String,
+    /// Device ID mixed into every MAC.
+    pub device_id: String,
+}
+
+impl PrefHashConfig {
+    /// Hasher for these values, failing with [`AppError::InvalidSeed`] if the
+    /// seed is not hex.
+    pub fn hasher(&self) -> AppResult<PrefHasher> {
+        PrefHasher::from_hex_seed(&self.seed, &self.device_id)
+    }
+}
+
+/// Variations country and features passed to Chrome on its command line.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::config::LaunchFlagsConfig;
+///
+/// let flags = LaunchFlagsConfig::default();
+/// assert_eq!(flags.country, "us");
+/// assert!(flags.features.contains(&"Glic".to_string()));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchFlagsConfig {
+    /// Value of `--variations-override-country`.
+    pub country: String,
+    /// Features for `--enable-features`.
+    pub features: Vec<String>,
+}
+
+impl Default for LaunchFlagsConfig {
+    fn default() -> Self {
+        Self {
+            country: DEFAULT_REGION.country.to_string(),
+            features: vec!["Glic".to_string(), "GlicRollout".to_string()],
+        }
+    }
+}
+
+/// Default config file location.
+///
+/// `$XDG_CONFIG_HOME/gemini-unlock/config.json` (or `~/.config/...`) on
+/// Linux and macOS, `%APPDATA%\gemini-unlock\config.json` on Windows.
+///
+/// # Examples
+///
+/// ```text
+/// let path = default_config_path()?;
+/// ```
+pub fn default_config_path() -> AppResult<PathBuf> {
+    let base = if cfg!(windows) {
+        PathBuf::from(
+            std::env::var("APPDATA").map_err(|_| AppError::MissingEnv("APPDATA".to_string()))?,
+        )
+    } else {
+        config_home()?
+    };
+    Ok(base.join("gemini-unlock").join(CONFIG_FILE))
+}
+
+/// `$XDG_CONFIG_HOME`, or `~/.config` if it is unset.
+///
+/// # Examples
+///
+/// ```text
+/// let flags_conf = config_home()?.join("chrome-flags.conf");
+/// ```
+pub fn config_home() -> AppResult<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => {
+            let home =
+                std::env::var("HOME").map_err(|_| AppError::MissingEnv("HOME".to_string()))?;
+            Ok(PathBuf::from(home).join(".config"))
+        }
+    }
+}
+
+/// Load a config file, returning defaults if it does not exist.
+///
+/// # Examples
+///
+/// ```text
+/// let config = load_config(&default_config_path()?)?;
+/// ```
+pub fn load_config(path: &Path) -> AppResult<Config> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(err) => return Err(err.into()),
+    };
+    let invalid =
+        |e: String| AppError::InvalidJson(format!("Config file {} is invalid: {e}", path.display()));
+    let config: Config = serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+    config
+        .shutdown
+        .validate()
+        .map_err(|e| invalid(format!("shutdown: {e}")))?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shutdown::StageAction;
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = load_config(&dir.path().join(CONFIG_FILE)).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn reads_shutdown_policy() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(
+            &path,
+            r#"{"shutdown":{"stages":[{"action":"term","timeout_ms":20000}]}}"#,
+        )
+        .unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.shutdown.stages.len(), 1);
+        assert_eq!(config.shutdown.stages[0].action, StageAction::Term);
+    }
+
+    #[test]
+    fn rejects_unusable_shutdown_policy() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        for shutdown in [
+            r#"{"stages":[]}"#,
+            r#"{"poll_interval_ms":0}"#,
+            r#"{"stages":[{"action":"kill","timeout_ms":18446744073709551615}]}"#,
+        ] {
+            fs::write(&path, format!(r#"{{"shutdown":{shutdown}}}"#)).unwrap();
+            assert!(
+                matches!(load_config(&path), Err(AppError::InvalidJson(_))),
+                "{shutdown}"
+            );
+        }
+    }
+
+    #[test]
+    fn reads_pref_hash_inputs() {
+        let config: Config =
+            serde_json::from_str(r#"{"pref_hash":{"seed":"00ff"}}"#).unwrap();
+        assert_eq!(config.pref_hash.seed, "00ff");
+        assert_eq!(config.pref_hash.device_id, "");
+    }
+
+    #[test]
+    fn launcher_flags_default_per_field() {
+        let config: Config =
+            serde_json::from_str(r#"{"launcher":{"country":"ca"}}"#).unwrap();
+        assert_eq!(config.launcher.country, "ca");
+        assert_eq!(config.launcher.features, LaunchFlagsConfig::default().features);
+    }
+
+    #[test]
+    fn rejects_invalid_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(&path, "{not json").unwrap();
+        assert!(matches!(load_config(&path), Err(AppError::InvalidJson(_))));
+    }
+}
@@ -0,0 +1,235 @@
+use serde_json::{Value, json};
+
+use crate::locale::DEFAULT_REGION;
+use crate::patcher::PatchRule;
+
+/// Key patterns related to Gemini eligibility; `*` matches any text and a
+/// pattern may match anywhere in a key, case-insensitively.
+pub const DISCOVERY_PATTERNS: &[&str] = &["glic", "gemini", "variations_*country*", "eligib*"];
+
+/// A field whose key, or an ancestor's key, matched a pattern.
+///
+/// # Examples
+///
+/// ```text
+/// Discovery { pointer: "/glic/is_eligible".into(), value: json!(false), pattern: "glic".into() }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Discovery {
+    /// JSON pointer of the field.
+    pub pointer: String,
+    /// Current value.
+    pub value: Value,
+    /// Pattern that matched.
+    pub pattern: String,
+}
+
+impl Discovery {
+    /// JSON type name of the value.
+    pub fn kind(&self) -> &'static str {
+        match self.value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+
+    /// Last key of the pointer.
+    fn key(&self) -> String {
+        self.pointer
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .replace("~1", "/")
+            .replace("~0", "~")
+    }
+}
+
+/// Whether `key` matches `pattern` anywhere, ignoring case.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::discover::key_matches;
+///
+/// assert!(key_matches("variations_*country*", "variations_permanent_consistency_country"));
+/// assert!(key_matches("eligib*", "is_glic_eligible"));
+/// assert!(!key_matches("variations_*country*", "variations_seed"));
+/// ```
+pub fn key_matches(pattern: &str, key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    let mut rest = key.as_str();
+    for part in pattern.to_ascii_lowercase().split('*') {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Every field in `json` whose key, or an ancestor's key, matches one of `patterns`.
+///
+/// Containers are reported before what they hold, in key order.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::discover::{discover_fields, DISCOVERY_PATTERNS};
+/// use serde_json::json;
+///
+/// let local_state = json!({"browser": {"glic": {"is_eligible": false}}, "other": 1});
+/// let found = discover_fields(&local_state, DISCOVERY_PATTERNS);
+/// let pointers: Vec<&str> = found.iter().map(|d| d.pointer.as_str()).collect();
+/// assert_eq!(pointers, ["/browser/glic", "/browser/glic/is_eligible"]);
+/// ```
+pub fn discover_fields<S: AsRef<str>>(json: &Value, patterns: &[S]) -> Vec<Discovery> {
+    let mut found = Vec::new();
+    walk(json, "", None, patterns, &mut found);
+    found
+}
+
+fn walk<S: AsRef<str>>(
+    value: &Value,
+    pointer: &str,
+    inherited: Option<&str>,
+    patterns: &[S],
+    found: &mut Vec<Discovery>,
+) {
+    let children: Vec<(String, &Value)> = match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, child)| (key.clone(), child))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, child)| (index.to_string(), child))
+            .collect(),
+        _ => return,
+    };
+    for (key, child) in children {
+        let child_pointer = format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"));
+        let matched = inherited.or_else(|| {
+            value
+                .is_object()
+                .then(|| {
+                    patterns
+                        .iter()
+                        .map(AsRef::as_ref)
+                        .find(|pattern| key_matches(pattern, &key))
+                })
+                .flatten()
+        });
+        if let Some(pattern) = matched {
+            found.push(Discovery {
+                pointer: child_pointer.clone(),
+                value: child.clone(),
+                pattern: pattern.to_string(),
+            });
+        }
+        walk(child, &child_pointer, matched, patterns, found);
+    }
+}
+
+/// Rule that would give a found field the value the patch gives its
+/// counterpart, if one can be derived from the key and value.
+///
+/// Booleans are switched on (off for `disable` keys); country strings and
+/// arrays get the region preset's country, keeping a leading version entry.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::discover::{suggest_rule, Discovery};
+/// use serde_json::json;
+///
+/// let found = Discovery { pointer: "/glic/is_eligible".into(), value: json!(false), pattern: "glic".into() };
+/// let rule = suggest_rule(&found).unwrap();
+/// assert_eq!(rule.value, json!(true));
+/// assert!(!rule.create);
+/// ```
+pub fn suggest_rule(found: &Discovery) -> Option<PatchRule> {
+    let key = found.key().to_ascii_lowercase();
+    let country = DEFAULT_REGION.country;
+    let value = match &found.value {
+        Value::Bool(_) => json!(!key.contains("disable")),
+        Value::String(_) if key.contains("country") => json!(country),
+        Value::Array(items) if key.contains("country") => match items.as_slice() {
+            [version, Value::String(_)] => json!([version, country]),
+            [Value::String(_)] | [] => json!([country]),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(PatchRule {
+        create: false,
+        ..PatchRule::set(&found.pointer, value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_nested_and_renamed_fields() {
+        let local_state = json!({
+            "is_glic_eligible": true,
+            "glic": {"eligibility": {"is_eligible": false}},
+            "variations_country": "de",
+            "variations_seed_signature": "abc",
+            "gemini~ai/settings": [1],
+            "profile": {"info_cache": {"Default": {"name": "Person 1"}}}
+        });
+        let found = discover_fields(&local_state, DISCOVERY_PATTERNS);
+        let pointers: Vec<&str> = found.iter().map(|d| d.pointer.as_str()).collect();
+        assert_eq!(
+            pointers,
+            [
+                "/gemini~0ai~1settings",
+                "/gemini~0ai~1settings/0",
+                "/glic",
+                "/glic/eligibility",
+                "/glic/eligibility/is_eligible",
+                "/is_glic_eligible",
+                "/variations_country",
+            ]
+        );
+        assert_eq!(found[4].kind(), "boolean");
+        assert_eq!(found[2].pattern, "glic");
+    }
+
+    #[test]
+    fn array_indices_are_not_keys() {
+        let found = discover_fields(&json!({"list": ["glic", {"glic": 1}]}), DISCOVERY_PATTERNS);
+        let pointers: Vec<&str> = found.iter().map(|d| d.pointer.as_str()).collect();
+        assert_eq!(pointers, ["/list/1/glic"]);
+    }
+
+    #[test]
+    fn suggests_rules_for_known_shapes() {
+        let suggest = |pointer: &str, value: Value| {
+            suggest_rule(&Discovery {
+                pointer: pointer.into(),
+                value,
+                pattern: "glic".into(),
+            })
+            .map(|rule| rule.value)
+        };
+        assert_eq!(
+            suggest("/glic/launcher_disabled", json!(true)),
+            Some(json!(false))
+        );
+        assert_eq!(suggest("/glic/country", json!("de")), Some(json!("us")));
+        assert_eq!(
+            suggest("/glic/consistency_country", json!(["140.0.7339.80", "de"])),
+            Some(json!(["140.0.7339.80", "us"]))
+        );
+        assert_eq!(suggest("/glic/rollout", json!(3)), None);
+        assert_eq!(suggest("/glic/settings", json!({})), None);
+    }
+}
@@ -33,6 +33,22 @@ pub enum AppError {
     #[error("Chrome is still running, please confirm it has been fully closed")]
     ChromeStillRunning,
 
+    /// Chrome was not closed before the `--wait` timeout.
+    #[error("Timed out after {0:?} waiting for Chrome to exit")]
+    WaitTimedOut(std::time::Duration),
+
+    /// The user-data dir is locked by Chrome on another host.
+    #[error("Chrome profile is in use on another host: {0}")]
+    ProfileLockedElsewhere(String),
+
+    /// No profile matches a `--profile` selector.
+    #[error("Profile not found: {0}")]
+    ProfileNotFound(String),
+
+    /// A `--profile` name matches several profiles.
+    #[error("Profile name {selector} is ambiguous, use one of the directories: {dirs}")]
+    AmbiguousProfile { selector: String, dirs: String },
+
     /// Chrome configuration file not found.
     #[error("Chrome configuration file not found: {0}")]
     ConfigNotFound(PathBuf),
@@ -41,13 +57,91 @@ pub enum AppError {
     #[error("Backup file not found: {0}")]
     BackupNotFound(PathBuf),
 
+    /// Chrome is older than every rule set.
+    #[error("Chrome {version} is not supported, Gemini needs Chrome {min} or newer")]
+    UnsupportedChromeVersion { version: String, min: u32 },
+
+    /// No installed browser binary for a channel.
+    #[error("No {0} binary found")]
+    BrowserNotFound(String),
+
+    /// No supported package manager is installed.
+    #[error("No apt, dnf5, dnf or pacman found below {0}")]
+    PackageManagerNotFound(PathBuf),
+
+    /// A root hook would run a binary that non-root users can replace.
+    #[error(
+        "Refusing to run {exe} as root, {path} is writable by non-root users; install gemini-unlock to a root-owned location such as /usr/local/bin"
+    )]
+    UntrustedExecutable { exe: PathBuf, path: PathBuf },
+
+    /// A file to replace was not written by this tool.
+    #[error("Refusing to overwrite {0}, it was not created by gemini-unlock")]
+    NotGenerated(PathBuf),
+
+    /// The tool's block in a flags file is damaged.
+    #[error("Malformed gemini-unlock block: {0}")]
+    MalformedBlock(String),
+
+    /// A chrome://flags name outside the curated catalog.
+    #[error("Unknown flag (see `flags list`): {0}")]
+    UnknownFlag(String),
+
+    /// A chrome://flags option index the flag does not have.
+    #[error("Flag {name} has no option {index} (choose 1 to {options})")]
+    InvalidFlagOption {
+        name: String,
+        index: usize,
+        options: usize,
+    },
+
     /// Invalid file path.
     #[error("Invalid path: {0}")]
     InvalidPath(PathBuf),
 
+    /// Not a BCP 47 language tag.
+    #[error("Invalid locale (expected a tag such as en-US): {0}")]
+    InvalidLocale(String),
+
+    /// Policy name or value not allowed by the embedded schema.
+    #[error("Invalid policy: {0}")]
+    InvalidPolicy(String),
+
+    /// Not a two-letter country code.
+    #[error("Invalid country (expected a code such as us): {0}")]
+    InvalidCountry(String),
+
+    /// Not a usable Chrome feature name.
+    #[error("Invalid feature name: {0}")]
+    InvalidFeature(String),
+
+    /// Not a usable login name.
+    #[error("Invalid user name: {0}")]
+    InvalidUser(String),
+
+    /// Pref hash seed is not valid hex.
+    #[error("Invalid pref hash seed (expected hex): {0}")]
+    InvalidSeed(String),
+
+    /// Doctor checks failed.
+    #[error("{0} doctor check(s) failed")]
+    DoctorFailed(usize),
+
+    /// A multi-file update failed and was rolled back.
+    #[error("Update failed and was rolled back: {0}")]
+    TransactionFailed(String),
+
     /// External command failure.
     #[error("Command execution failed: {command} ({details})")]
     CommandFailed { command: String, details: String },
+
+    /// Sending a signal to a process failed.
+    #[error("Failed to send SIG{signal} to process {pid}: {source}")]
+    SignalFailed {
+        pid: i32,
+        signal: &'static str,
+        source: std::io::Error,
+    },
 }
 
 /// Application-level Result type alias.
@@ -0,0 +1,260 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+
+/// Marker line near the top of every file the tool generates.
+pub const GENERATED_MARKER: &str = "# Generated by gemini-unlock";
+
+/// Whether a file was generated by the tool, so it may be replaced or removed.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::files::is_generated;
+///
+/// assert!(is_generated("#!/bin/sh\n# Generated by gemini-unlock\nexec chrome\n"));
+/// assert!(is_generated("// Generated by gemini-unlock\nDPkg::Post-Invoke {};\n"));
+/// assert!(!is_generated("[Desktop Entry]\n"));
+/// ```
+pub fn is_generated(content: &str) -> bool {
+    let marker = GENERATED_MARKER.trim_start_matches("# ");
+    content.lines().take(2).any(|line| {
+        line.strip_prefix('#')
+            .or_else(|| line.strip_prefix("//"))
+            .is_some_and(|rest| rest.trim_start().starts_with(marker))
+    })
+}
+
+/// Fail with [`AppError::NotGenerated`] if `path` exists but was not
+/// generated by the tool, so it must not be replaced.
+///
+/// # Examples
+///
+/// ```text
+/// ensure_replaceable(&paths.wrapper)?;
+/// ```
+pub fn ensure_replaceable(path: &Path) -> AppResult<()> {
+    match fs::read_to_string(path) {
+        Ok(content) if !is_generated(&content) => Err(AppError::NotGenerated(path.to_path_buf())),
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// What [`remove_generated`] did with a file.
+///
+/// # Examples
+///
+/// ```text
+/// Removal::Kept // the file was not generated by the tool
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Removal {
+    /// The file was generated by the tool and is gone now.
+    Removed,
+    /// The file was not generated by the tool and was left alone.
+    Kept,
+    /// There was no file.
+    Missing,
+}
+
+/// Remove `path` if the tool generated it.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::files::{remove_generated, Removal};
+///
+/// let dir = tempfile::TempDir::new().unwrap();
+/// let path = dir.path().join("gemini-unlock.hook");
+/// std::fs::write(&path, "# Generated by gemini-unlock\n").unwrap();
+/// assert_eq!(remove_generated(&path).unwrap(), Removal::Removed);
+/// assert_eq!(remove_generated(&path).unwrap(), Removal::Missing);
+/// ```
+pub fn remove_generated(path: &Path) -> AppResult<Removal> {
+    match fs::read_to_string(path) {
+        Ok(content) if is_generated(&content) => {
+            fs::remove_file(path)?;
+            Ok(Removal::Removed)
+        }
+        Ok(_) => Ok(Removal::Kept),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Removal::Missing),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Backup path for a file: the same name with `.bak` appended.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::files::backup_path;
+///
+/// let backup = backup_path(Path::new("/tmp/Local State")).unwrap();
+/// assert_eq!(backup, Path::new("/tmp/Local State.bak"));
+/// ```
+pub fn backup_path(path: &Path) -> AppResult<PathBuf> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| path.with_file_name(format!("{name}.bak")))
+        .ok_or_else(|| AppError::InvalidPath(path.to_path_buf()))
+}
+
+/// Copy a file to its backup path, returning the backup path.
+///
+/// # Examples
+///
+/// ```text
+/// let backup = backup_file(&preferences)?;
+/// ```
+pub fn backup_file(path: &Path) -> AppResult<PathBuf> {
+    let backup = backup_path(path)?;
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// Temporary sibling used while writing `path`.
+pub(crate) fn staging_path(path: &Path, suffix: &str) -> AppResult<PathBuf> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| path.with_file_name(format!(".{name}.{suffix}")))
+        .ok_or_else(|| AppError::InvalidPath(path.to_path_buf()))
+}
+
+/// Write a file atomically: write a sibling temp file, sync it, then rename
+/// it over the target so readers never see a partial file.
+///
+/// Existing permissions are kept.
+///
+/// # Examples
+///
+/// ```text
+/// write_atomic(&chrome_state, &report.content)?;
+/// ```
+pub fn write_atomic(path: &Path, content: &str) -> AppResult<()> {
+    let temp = staging_path(path, "tmp")?;
+    let result = (|| {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(meta) = fs::metadata(path) {
+            fs::set_permissions(&temp, meta.permissions())?;
+        }
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    Ok(result?)
+}
+
+/// Whether this process may write to `path`.
+///
+/// # Examples
+///
+/// ```text
+/// if !is_writable(&chrome_state) { .. }
+/// ```
+#[cfg(unix)]
+pub fn is_writable(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a valid NUL-terminated string for the duration of the call.
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn is_writable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| !meta.permissions().readonly())
+}
+
+/// First of `path` (symlinks resolved) and its ancestors that a non-root user
+/// could replace: not owned by root, or group- or world-writable.
+///
+/// # Examples
+///
+/// ```text
+/// untrusted_ancestor(Path::new("/usr/local/bin/gemini-unlock")) // None
+/// untrusted_ancestor(Path::new("/home/alice/.cargo/bin/gemini-unlock")) // Some("/home/alice/.cargo/bin/gemini-unlock")
+/// ```
+#[cfg(unix)]
+pub fn untrusted_ancestor(path: &Path) -> AppResult<Option<PathBuf>> {
+    use std::os::unix::fs::MetadataExt;
+    let path = fs::canonicalize(path)?;
+    for candidate in path.ancestors() {
+        let meta = fs::metadata(candidate)?;
+        if meta.uid() != 0 || meta.mode() & 0o022 != 0 {
+            return Ok(Some(candidate.to_path_buf()));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(not(unix))]
+pub fn untrusted_ancestor(_path: &Path) -> AppResult<Option<PathBuf>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn writable_paths_are_untrusted() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("gemini-unlock");
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o777)).unwrap();
+        let path = fs::canonicalize(&path).unwrap();
+        assert_eq!(untrusted_ancestor(&path).unwrap(), Some(path));
+        assert_eq!(untrusted_ancestor(Path::new("/")).unwrap(), None);
+    }
+
+    #[test]
+    fn foreign_files_are_kept() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("google-chrome.desktop");
+        fs::write(&path, "[Desktop Entry]\n").unwrap();
+        assert!(matches!(
+            ensure_replaceable(&path),
+            Err(AppError::NotGenerated(_))
+        ));
+        assert_eq!(remove_generated(&path).unwrap(), Removal::Kept);
+        assert!(path.exists());
+        assert!(ensure_replaceable(&dir.path().join("missing")).is_ok());
+    }
+
+    #[test]
+    fn writes_atomically_and_backs_up() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("Preferences");
+        fs::write(&path, "old").unwrap();
+
+        let backup = backup_file(&path).unwrap();
+        write_atomic(&path, "new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_to_string(backup).unwrap(), "old");
+        // No temp file left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn failed_write_leaves_no_temp_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        // Renaming a file over a directory fails
+        let path = dir.path().join("target");
+        fs::create_dir_all(path.join("child")).unwrap();
+
+        assert!(write_atomic(&path, "data").is_err());
+        assert!(!staging_path(&path, "tmp").unwrap().exists());
+    }
+}
@@ -0,0 +1,264 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+use crate::files::write_atomic;
+use crate::process::ChromeChannel;
+
+/// First line of the tool's block in a flags file.
+pub const BLOCK_BEGIN: &str = "# >>> gemini-unlock >>>";
+
+/// Last line of the tool's block in a flags file.
+pub const BLOCK_END: &str = "# <<< gemini-unlock <<<";
+
+/// Flags file read by a channel's distro wrapper script.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::flagsconf::flags_conf_name;
+/// use gemini_unlock::process::ChromeChannel;
+///
+/// assert_eq!(flags_conf_name(ChromeChannel::Chromium), "chromium-flags.conf");
+/// ```
+pub fn flags_conf_name(channel: ChromeChannel) -> &'static str {
+    match channel {
+        ChromeChannel::Stable | ChromeChannel::Unknown => "chrome-flags.conf",
+        ChromeChannel::Beta => "chrome-beta-flags.conf",
+        ChromeChannel::Dev => "chrome-dev-flags.conf",
+        ChromeChannel::Canary => "chrome-canary-flags.conf",
+        ChromeChannel::Chromium => "chromium-flags.conf",
+    }
+}
+
+/// Path of a channel's flags file below `config_home`.
+///
+/// # Examples
+///
+/// ```text
+/// let path = flags_conf_path(ChromeChannel::Stable, &config_home()?);
+/// ```
+pub fn flags_conf_path(channel: ChromeChannel, config_home: &Path) -> PathBuf {
+    config_home.join(flags_conf_name(channel))
+}
+
+/// Line range of the tool's block, markers included.
+fn block_range(lines: &[&str]) -> AppResult<Option<(usize, usize)>> {
+    let Some(begin) = lines.iter().position(|line| line.trim() == BLOCK_BEGIN) else {
+        return Ok(None);
+    };
+    let end = lines[begin..]
+        .iter()
+        .position(|line| line.trim() == BLOCK_END)
+        .ok_or_else(|| {
+            AppError::MalformedBlock(format!("{BLOCK_BEGIN} without {BLOCK_END}"))
+        })?;
+    Ok(Some((begin, begin + end)))
+}
+
+/// Text of the tool's block, markers included, if there is one.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::flagsconf::block_text;
+///
+/// let content = "--ozone-platform=wayland\n# >>> gemini-unlock >>>\n--variations-override-country=us\n# <<< gemini-unlock <<<\n";
+/// assert!(block_text(content).unwrap().unwrap().contains("--variations-override-country=us"));
+/// assert_eq!(block_text("--ozone-platform=wayland\n").unwrap(), None);
+/// ```
+pub fn block_text(content: &str) -> AppResult<Option<String>> {
+    let lines: Vec<&str> = content.lines().collect();
+    Ok(block_range(&lines)?.map(|(begin, end)| lines[begin..=end].join("\n") + "\n"))
+}
+
+/// Flags in the tool's block, if there is one.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::flagsconf::{block_flags, set_block};
+///
+/// let content = set_block("", &["--variations-override-country=us".into()]).unwrap();
+/// assert_eq!(block_flags(&content).unwrap(), Some(vec!["--variations-override-country=us".to_string()]));
+/// ```
+pub fn block_flags(content: &str) -> AppResult<Option<Vec<String>>> {
+    let lines: Vec<&str> = content.lines().collect();
+    Ok(block_range(&lines)?.map(|(begin, end)| {
+        lines[begin + 1..end]
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect()
+    }))
+}
+
+/// Add or replace the tool's block, leaving every other line alone.
+///
+/// A new block is appended; an existing one is updated in place, so running
+/// twice gives the same file.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::flagsconf::set_block;
+///
+/// let flags = vec!["--variations-override-country=us".to_string()];
+/// let once = set_block("--ozone-platform=wayland\n", &flags).unwrap();
+/// assert!(once.starts_with("--ozone-platform=wayland\n\n# >>> gemini-unlock >>>\n"));
+/// assert_eq!(set_block(&once, &flags).unwrap(), once);
+/// ```
+pub fn set_block(content: &str, flags: &[String]) -> AppResult<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut block = vec![BLOCK_BEGIN];
+    block.extend(flags.iter().map(String::as_str));
+    block.push(BLOCK_END);
+
+    let out = match block_range(&lines)? {
+        Some((begin, end)) => [&lines[..begin], &block[..], &lines[end + 1..]].concat(),
+        None if lines.iter().all(|line| line.trim().is_empty()) => block,
+        None => {
+            let mut out = lines;
+            if out.last().is_some_and(|line| !line.trim().is_empty()) {
+                out.push("");
+            }
+            out.extend(block);
+            out
+        }
+    };
+    Ok(out.join("\n") + "\n")
+}
+
+/// Remove the tool's block, or return `None` if there is none.
+///
+/// The blank line [`set_block`] put before the block goes with it.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::flagsconf::{remove_block, set_block};
+///
+/// let user = "--ozone-platform=wayland\n";
+/// let patched = set_block(user, &["--enable-features=Glic".into()]).unwrap();
+/// assert_eq!(remove_block(&patched).unwrap().as_deref(), Some(user));
+/// assert_eq!(remove_block(user).unwrap(), None);
+/// ```
+pub fn remove_block(content: &str) -> AppResult<Option<String>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some((mut begin, end)) = block_range(&lines)? else {
+        return Ok(None);
+    };
+    if begin > 0 && lines[begin - 1].trim().is_empty() {
+        begin -= 1;
+    }
+    let out = [&lines[..begin], &lines[end + 1..]].concat();
+    if out.is_empty() {
+        return Ok(Some(String::new()));
+    }
+    Ok(Some(out.join("\n") + "\n"))
+}
+
+/// What [`remove_block_from`] did with a flags file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BlockRemoval {
+    /// The file only held the block and was removed.
+    FileRemoved,
+    /// The block was cut out and the user's lines were kept.
+    BlockRemoved,
+    /// The file is missing or has no block.
+    NoBlock,
+}
+
+/// Remove the tool's block from the flags file at `path`, and the file
+/// itself if nothing but blank lines is left.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::flagsconf::{remove_block_from, set_block, BlockRemoval};
+///
+/// let dir = tempfile::TempDir::new().unwrap();
+/// let path = dir.path().join("chrome-flags.conf");
+/// std::fs::write(&path, set_block("", &["--enable-features=Glic".into()]).unwrap()).unwrap();
+/// assert_eq!(remove_block_from(&path).unwrap(), BlockRemoval::FileRemoved);
+/// assert_eq!(remove_block_from(&path).unwrap(), BlockRemoval::NoBlock);
+/// ```
+pub fn remove_block_from(path: &Path) -> AppResult<BlockRemoval> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BlockRemoval::NoBlock),
+        Err(err) => return Err(err.into()),
+    };
+    match remove_block(&content)? {
+        Some(rest) if rest.trim().is_empty() => {
+            fs::remove_file(path)?;
+            Ok(BlockRemoval::FileRemoved)
+        }
+        Some(rest) => {
+            write_atomic(path, &rest)?;
+            Ok(BlockRemoval::BlockRemoved)
+        }
+        None => Ok(BlockRemoval::NoBlock),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(country: &str) -> Vec<String> {
+        vec![
+            format!("--variations-override-country={country}"),
+            "--enable-features=Glic".to_string(),
+        ]
+    }
+
+    #[test]
+    fn updates_block_in_place() {
+        let user = "# my flags\n--ozone-platform=wayland\n";
+        let first = set_block(user, &flags("us")).unwrap();
+        let edited = first.clone() + "--force-dark-mode\n";
+
+        let second = set_block(&edited, &flags("ca")).unwrap();
+        assert!(second.starts_with(user));
+        assert!(second.ends_with("# <<< gemini-unlock <<<\n--force-dark-mode\n"));
+        assert_eq!(
+            block_flags(&second).unwrap().unwrap()[0],
+            "--variations-override-country=ca"
+        );
+
+        let removed = remove_block(&second).unwrap().unwrap();
+        assert_eq!(removed, format!("{user}--force-dark-mode\n"));
+    }
+
+    #[test]
+    fn creates_file_content_from_nothing() {
+        let content = set_block("", &flags("us")).unwrap();
+        assert!(content.starts_with(BLOCK_BEGIN));
+        assert_eq!(remove_block(&content).unwrap().as_deref(), Some(""));
+    }
+
+    #[test]
+    fn rejects_unterminated_block() {
+        let broken = format!("{BLOCK_BEGIN}\n--enable-features=Glic\n--user-flag\n");
+        assert!(matches!(
+            set_block(&broken, &flags("us")),
+            Err(AppError::MalformedBlock(_))
+        ));
+        assert!(remove_block(&broken).is_err());
+    }
+
+    #[test]
+    fn removes_file_left_with_blank_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("chrome-flags.conf");
+        fs::write(
+            &path,
+            format!("\n\n{}", set_block("", &flags("us")).unwrap()),
+        )
+        .unwrap();
+        assert_eq!(remove_block_from(&path).unwrap(), BlockRemoval::FileRemoved);
+        assert!(!path.exists());
+    }
+}
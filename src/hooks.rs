@@ -0,0 +1,485 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::{AppError, AppResult};
+use crate::files::{GENERATED_MARKER, ensure_replaceable, untrusted_ancestor, write_atomic};
+use crate::launcher::shell_quote;
+use crate::manifest::{Artifact, ArtifactKind, Manifest};
+use crate::platform::run_as_user;
+
+/// Opt-in list of `hook run`, relative to the hook root.
+pub const HOOK_USERS_FILE: &str = "etc/gemini-unlock/hook-users";
+
+/// Package managers a post-transaction hook can be installed for.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::hooks::PackageManager;
+///
+/// assert_eq!("pacman".parse::<PackageManager>().unwrap(), PackageManager::Pacman);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PackageManager {
+    /// Debian and Ubuntu; a `DPkg::Post-Invoke` snippet.
+    Apt,
+    /// Fedora up to 40; an action of the dnf4 post-transaction-actions plugin.
+    Dnf,
+    /// Fedora 41 and later; an action of the libdnf5 actions plugin.
+    Dnf5,
+    /// Arch Linux; an alpm `.hook` file.
+    Pacman,
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Apt => "apt",
+            Self::Dnf => "dnf",
+            Self::Dnf5 => "dnf5",
+            Self::Pacman => "pacman",
+        })
+    }
+}
+
+impl FromStr for PackageManager {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "apt" => Ok(Self::Apt),
+            "dnf" => Ok(Self::Dnf),
+            "dnf5" => Ok(Self::Dnf5),
+            "pacman" => Ok(Self::Pacman),
+            other => Err(format!("unknown package manager: {other}")),
+        }
+    }
+}
+
+impl PackageManager {
+    /// Every supported package manager, in detection order; dnf5 comes
+    /// first since it also installs `dnf` as a link to itself.
+    pub const ALL: [Self; 4] = [Self::Apt, Self::Dnf5, Self::Dnf, Self::Pacman];
+
+    /// Where the hook file lives below `root`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use gemini_unlock::hooks::PackageManager;
+    ///
+    /// let path = PackageManager::Apt.hook_path(Path::new("/"));
+    /// assert_eq!(path, Path::new("/etc/apt/apt.conf.d/99gemini-unlock"));
+    /// ```
+    pub fn hook_path(&self, root: &Path) -> PathBuf {
+        root.join(match self {
+            Self::Apt => "etc/apt/apt.conf.d/99gemini-unlock",
+            Self::Dnf => "etc/dnf/plugins/post-transaction-actions.d/gemini-unlock.action",
+            Self::Dnf5 => "etc/dnf/libdnf5-plugins/actions.d/gemini-unlock.actions",
+            Self::Pacman => "etc/pacman.d/hooks/gemini-unlock.hook",
+        })
+    }
+
+    /// The package manager's binary, relative to the root.
+    fn binary(&self) -> &'static str {
+        match self {
+            Self::Apt => "usr/bin/apt-get",
+            Self::Dnf => "usr/bin/dnf",
+            Self::Dnf5 => "usr/bin/dnf5",
+            Self::Pacman => "usr/bin/pacman",
+        }
+    }
+
+    /// First package manager installed below `root`.
+    ///
+    /// # Examples
+    ///
+    /// ```text
+    /// let manager = PackageManager::detect(Path::new("/"))?;
+    /// ```
+    pub fn detect(root: &Path) -> AppResult<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|manager| root.join(manager.binary()).is_file())
+            .ok_or_else(|| AppError::PackageManagerNotFound(root.to_path_buf()))
+    }
+}
+
+/// Hook file running `command` after package transactions.
+///
+/// dnf and pacman only fire for Chrome and Chromium packages; apt's
+/// `Post-Invoke` cannot filter, so it runs after every dpkg call and never
+/// fails the transaction. dnf5 runs the command without a shell, splitting
+/// it at spaces, so its arguments are not quoted.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::hooks::{hook_file, PackageManager};
+///
+/// let hook = hook_file(PackageManager::Pacman, &["/usr/bin/gemini-unlock".into(), "hook".into(), "run".into()]);
+/// assert!(hook.contains("\nWhen = PostTransaction\nExec = '/usr/bin/gemini-unlock' 'hook' 'run'\n"));
+/// ```
+pub fn hook_file(manager: PackageManager, command: &[String]) -> String {
+    let exec = command
+        .iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let note = "remove with `gemini-unlock hook remove`";
+    match manager {
+        PackageManager::Apt => format!(
+            "// {}; {note}\nDPkg::Post-Invoke {{ \"if [ -x {} ]; then {exec} || true; fi\"; }};\n",
+            GENERATED_MARKER.trim_start_matches("# "),
+            shell_quote(&command[0])
+        ),
+        PackageManager::Dnf => {
+            format!("{GENERATED_MARKER}; {note}\ngoogle-chrome-*:in:{exec}\nchromium*:in:{exec}\n")
+        }
+        PackageManager::Dnf5 => {
+            let exec = command.join(" ");
+            format!(
+                "{GENERATED_MARKER}; {note}\n\
+                 post_transaction:google-chrome-*:in::{exec}\n\
+                 post_transaction:chromium*:in::{exec}\n"
+            )
+        }
+        PackageManager::Pacman => format!(
+            "{GENERATED_MARKER}; {note}\n[Trigger]\nOperation = Install\nOperation = Upgrade\n\
+             Type = Package\nTarget = google-chrome*\nTarget = chromium\n\n\
+             [Action]\nDescription = Re-applying the Chrome Gemini patch...\n\
+             When = PostTransaction\nExec = {exec}\n"
+        ),
+    }
+}
+
+/// Check that `name` looks like a login name.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::hooks::validate_user;
+///
+/// assert!(validate_user("alice").is_ok());
+/// assert!(validate_user("-rf").is_err());
+/// ```
+pub fn validate_user(name: &str) -> AppResult<()> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidUser(name.to_string()))
+    }
+}
+
+/// Users in the opt-in list, skipping comments and blank lines.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::hooks::parse_users;
+///
+/// assert_eq!(parse_users("# header\nalice\n\nbob\n"), ["alice", "bob"]);
+/// ```
+pub fn parse_users(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Opt-in list with one user per line.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::hooks::{parse_users, users_file};
+///
+/// let users = vec!["alice".to_string()];
+/// assert_eq!(parse_users(&users_file(&users)), users);
+/// ```
+pub fn users_file(users: &[String]) -> String {
+    let mut content = format!("{GENERATED_MARKER}; users whose Chrome `hook run` re-patches\n");
+    for user in users {
+        content.push_str(user);
+        content.push('\n');
+    }
+    content
+}
+
+/// Write the hook running `exe hook run` for `manager` below `root`, add
+/// `users` to the opt-in list and record both in the manifest.
+///
+/// The hook runs as root after every transaction, so `exe` and its
+/// directories must not be replaceable by other users. Returns the hook path
+/// and every opted-in user.
+///
+/// # Examples
+///
+/// ```text
+/// let (path, users) = install_hook(Path::new("/"), PackageManager::Pacman, &["alice".into()], &exe, &manifest)?;
+/// ```
+pub fn install_hook(
+    root: &Path,
+    manager: PackageManager,
+    users: &[String],
+    exe: &Path,
+    manifest_path: &Path,
+) -> AppResult<(PathBuf, Vec<String>)> {
+    for user in users {
+        validate_user(user)?;
+    }
+    let path = manager.hook_path(root);
+    ensure_replaceable(&path)?;
+    if let Some(untrusted) = untrusted_ancestor(exe)? {
+        return Err(AppError::UntrustedExecutable {
+            exe: exe.to_path_buf(),
+            path: untrusted,
+        });
+    }
+
+    let command = vec![exe.display().to_string(), "hook".into(), "run".into()];
+    let content = hook_file(manager, &command);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(&path, &content)?;
+    let mut manifest = Manifest::load(manifest_path)?;
+    manifest.record(Artifact::new(ArtifactKind::PackageHook, &path, &content));
+    manifest.save(manifest_path)?;
+
+    let mut opted_in = read_hook_users(root)?;
+    for user in users {
+        if !opted_in.contains(user) {
+            opted_in.push(user.clone());
+        }
+    }
+    write_hook_users(root, &opted_in, manifest_path)?;
+    Ok((path, opted_in))
+}
+
+/// Re-apply the patch as each opted-in user with `exe watch --once`.
+///
+/// One user failing does not stop the others; returns the users it failed
+/// for, with the error.
+///
+/// # Examples
+///
+/// ```text
+/// for (user, err) in run_hook(Path::new("/"), &std::env::current_exe()?)? { .. }
+/// ```
+pub fn run_hook(root: &Path, exe: &Path) -> AppResult<Vec<(String, AppError)>> {
+    run_hook_with(root, exe, run_as_user)
+}
+
+fn run_hook_with(
+    root: &Path,
+    exe: &Path,
+    run: impl Fn(&str, &[String]) -> AppResult<()>,
+) -> AppResult<Vec<(String, AppError)>> {
+    let users = read_hook_users(root)?;
+    if users.is_empty() {
+        log::info!("No user has opted in to the package hook");
+    }
+    let command: Vec<String> = [
+        &exe.display().to_string(),
+        "watch",
+        "--once",
+        "--interval",
+        "100ms",
+        "--settle",
+        "0s",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+
+    let mut failed = Vec::new();
+    for user in users {
+        log::info!("Re-applying the patch for {user}");
+        if let Err(err) = validate_user(&user).and_then(|()| run(&user, &command)) {
+            log::warn!("Hook run failed for {user}: {err}");
+            failed.push((user, err));
+        }
+    }
+    Ok(failed)
+}
+
+/// Users in the opt-in list below `root`, empty if there is none.
+///
+/// # Examples
+///
+/// ```text
+/// let users = read_hook_users(Path::new("/"))?;
+/// ```
+pub fn read_hook_users(root: &Path) -> AppResult<Vec<String>> {
+    match fs::read_to_string(root.join(HOOK_USERS_FILE)) {
+        Ok(content) => Ok(parse_users(&content)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Write the opt-in list below `root` and record it in the manifest.
+///
+/// # Examples
+///
+/// ```text
+/// write_hook_users(Path::new("/"), &["alice".into()], &manifest)?;
+/// ```
+pub fn write_hook_users(root: &Path, users: &[String], manifest_path: &Path) -> AppResult<()> {
+    let path = root.join(HOOK_USERS_FILE);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = users_file(users);
+    write_atomic(&path, &content)?;
+    let mut manifest = Manifest::load(manifest_path)?;
+    manifest.record(Artifact::new(ArtifactKind::HookUsers, &path, &content));
+    manifest.save(manifest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::files::is_generated;
+
+    fn command() -> Vec<String> {
+        vec!["/usr/bin/gemini-unlock".into(), "hook".into(), "run".into()]
+    }
+
+    #[test]
+    fn hooks_are_recognized_as_generated() {
+        for manager in PackageManager::ALL {
+            let hook = hook_file(manager, &command());
+            assert!(is_generated(&hook), "{manager}");
+        }
+    }
+
+    #[test]
+    fn apt_hook_never_fails_dpkg() {
+        let hook = hook_file(PackageManager::Apt, &command());
+        assert!(hook.contains(
+            "DPkg::Post-Invoke { \"if [ -x '/usr/bin/gemini-unlock' ]; then '/usr/bin/gemini-unlock' 'hook' 'run' || true; fi\"; };"
+        ));
+    }
+
+    #[test]
+    fn detects_installed_manager() {
+        let root = tempfile::TempDir::new().unwrap();
+        assert!(matches!(
+            PackageManager::detect(root.path()),
+            Err(AppError::PackageManagerNotFound(_))
+        ));
+        std::fs::create_dir_all(root.path().join("usr/bin")).unwrap();
+        std::fs::write(root.path().join("usr/bin/dnf"), "").unwrap();
+        assert_eq!(
+            PackageManager::detect(root.path()).unwrap(),
+            PackageManager::Dnf
+        );
+        std::fs::write(root.path().join("usr/bin/dnf5"), "").unwrap();
+        assert_eq!(
+            PackageManager::detect(root.path()).unwrap(),
+            PackageManager::Dnf5
+        );
+    }
+
+    #[test]
+    fn dnf5_actions_are_unquoted() {
+        let hook = hook_file(PackageManager::Dnf5, &command());
+        assert!(
+            hook.contains(
+                "\npost_transaction:google-chrome-*:in::/usr/bin/gemini-unlock hook run\n"
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_hook_records_hook_and_users() {
+        let root = tempfile::TempDir::new().unwrap();
+        let manifest_path = root.path().join("installed.json");
+        let exe = Path::new("/bin/sh");
+        let (path, users) = install_hook(
+            root.path(),
+            PackageManager::Pacman,
+            &["alice".into()],
+            exe,
+            &manifest_path,
+        )
+        .unwrap();
+        assert_eq!(users, ["alice"]);
+        assert!(
+            fs::read_to_string(&path)
+                .unwrap()
+                .contains("Exec = '/bin/sh' 'hook' 'run'")
+        );
+
+        let (_, users) = install_hook(
+            root.path(),
+            PackageManager::Pacman,
+            &["bob".into(), "alice".into()],
+            exe,
+            &manifest_path,
+        )
+        .unwrap();
+        assert_eq!(users, ["alice", "bob"]);
+        assert_eq!(read_hook_users(root.path()).unwrap(), users);
+        let manifest = Manifest::load(&manifest_path).unwrap();
+        assert_eq!(manifest.artifacts.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_hook_refuses_user_writable_binary() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = tempfile::TempDir::new().unwrap();
+        let exe = root.path().join("gemini-unlock");
+        fs::write(&exe, "").unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o777)).unwrap();
+        let manifest_path = root.path().join("installed.json");
+        assert!(matches!(
+            install_hook(root.path(), PackageManager::Apt, &[], &exe, &manifest_path),
+            Err(AppError::UntrustedExecutable { .. })
+        ));
+        assert!(!PackageManager::Apt.hook_path(root.path()).exists());
+        assert!(!manifest_path.exists());
+    }
+
+    #[test]
+    fn run_hook_continues_after_a_failing_user() {
+        let root = tempfile::TempDir::new().unwrap();
+        let manifest_path = root.path().join("installed.json");
+        let users = vec!["alice".to_string(), "bob".to_string(), "carol".to_string()];
+        write_hook_users(root.path(), &users, &manifest_path).unwrap();
+
+        let ran = std::cell::RefCell::new(Vec::new());
+        let failed = run_hook_with(
+            root.path(),
+            Path::new("/usr/bin/gemini-unlock"),
+            |user, command| {
+                assert_eq!(command[..3], ["/usr/bin/gemini-unlock", "watch", "--once"]);
+                ran.borrow_mut().push(user.to_string());
+                if user == "bob" {
+                    return Err(AppError::InvalidUser(user.to_string()));
+                }
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(ran.into_inner(), users);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "bob");
+    }
+}
@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use crate::error::AppResult;
+use crate::locale::accept_languages_rules;
+use crate::patcher::{PatchRule, RuleReport, apply_rules, preferences_rules};
+use crate::platform::OsKind;
+
+/// Where Chrome looks for its initial preferences on each system.
+///
+/// Linux and Windows read `initial_preferences` next to the Chrome binary;
+/// macOS reads a system-wide file.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::initialprefs::default_initial_preferences_path;
+/// use gemini_unlock::platform::OsKind;
+///
+/// let path = default_initial_preferences_path(OsKind::Linux);
+/// assert_eq!(path, Path::new("/opt/google/chrome/initial_preferences"));
+/// ```
+pub fn default_initial_preferences_path(os: OsKind) -> PathBuf {
+    match os {
+        OsKind::Linux => PathBuf::from("/opt/google/chrome/initial_preferences"),
+        OsKind::Macos => PathBuf::from("/Library/Google/Google Chrome Initial Preferences"),
+        OsKind::Windows => {
+            PathBuf::from(r"C:\Program Files\Google\Chrome\Application\initial_preferences")
+        }
+    }
+}
+
+/// Rules for a fresh install's first profile: the Preferences rule set and
+/// the content languages of `locale`, plus first-run switches if requested.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::initialprefs::initial_preferences_rules;
+///
+/// let rules = initial_preferences_rules("en-US", false);
+/// assert!(rules.iter().any(|r| r.pointer == "/glic/launcher_enabled"));
+/// assert!(rules.iter().any(|r| r.pointer == "/intl/accept_languages"));
+/// ```
+pub fn initial_preferences_rules(locale: &str, skip_first_run: bool) -> Vec<PatchRule> {
+    let mut rules = preferences_rules();
+    rules.extend(accept_languages_rules(locale));
+    if skip_first_run {
+        rules.extend([
+            PatchRule::set("/distribution/skip_first_run_ui", json!(true)),
+            PatchRule::set(
+                "/distribution/suppress_first_run_default_browser_prompt",
+                json!(true),
+            ),
+        ]);
+    }
+    rules
+}
+
+/// Initial preferences with the rules applied to `existing` (or an empty
+/// file), so settings a distribution already ships are kept.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::initialprefs::initial_preferences;
+///
+/// let report = initial_preferences(None, "en-US", true).unwrap();
+/// assert!(report.content.contains(r#""skip_first_run_ui":true"#));
+/// ```
+pub fn initial_preferences(
+    existing: Option<&str>,
+    locale: &str,
+    skip_first_run: bool,
+) -> AppResult<RuleReport> {
+    apply_rules(
+        existing.unwrap_or("{}"),
+        &initial_preferences_rules(locale, skip_first_run),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn keeps_distribution_settings() {
+        let existing = r#"{"distribution":{"import_bookmarks":false},"homepage":"https://example.com"}"#;
+        let report = initial_preferences(Some(existing), "de-DE", true).unwrap();
+        let prefs: Value = serde_json::from_str(&report.content).unwrap();
+        assert_eq!(prefs["homepage"], "https://example.com");
+        assert_eq!(prefs["distribution"]["import_bookmarks"], false);
+        assert_eq!(prefs["distribution"]["skip_first_run_ui"], true);
+        assert_eq!(prefs["glic"]["launcher_enabled"], true);
+        assert_eq!(prefs["intl"]["accept_languages"], "de-DE,de");
+    }
+
+    #[test]
+    fn first_run_switches_are_optional() {
+        let report = initial_preferences(None, "en-US", false).unwrap();
+        let prefs: Value = serde_json::from_str(&report.content).unwrap();
+        assert!(prefs.get("distribution").is_none());
+        assert_eq!(report.changes.len(), 2);
+    }
+}
@@ -0,0 +1,207 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+use crate::patcher::{FieldChange, remove_pointer, set_pointer};
+
+/// Unpatch journal file name, kept next to `Local State`.
+pub const JOURNAL_FILE: &str = "gemini-unlock-journal.json";
+
+/// Original value of one field changed by the tool.
+///
+/// # Examples
+///
+/// ```text
+/// JournalEntry { file: "Local State".into(), pointer: "/intl/app_locale".into(), old: Some(json!("de")) }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// File relative to the user-data dir, e.g. `Default/Preferences`.
+    pub file: String,
+    /// JSON pointer of the field.
+    pub pointer: String,
+    /// Value before the first change; `None` if the tool created the field.
+    pub old: Option<Value>,
+}
+
+/// Original values of every field the tool changed, used to unpatch.
+///
+/// Only the first change of a field is kept, so the journal still holds
+/// the user's own value after repeated runs overwrite the `.bak` backups.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::journal::Journal;
+/// use gemini_unlock::patcher::FieldChange;
+/// use serde_json::json;
+///
+/// let mut journal = Journal::default();
+/// let change = FieldChange { pointer: "/intl/app_locale".into(), old: Some(json!("de")), new: json!("en-US") };
+/// journal.record("Local State", &[change.clone()]);
+/// journal.record("Local State", &[FieldChange { old: Some(json!("fr")), ..change }]);
+/// assert_eq!(journal.entries[0].old, Some(json!("de")));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Journal {
+    /// Recorded fields, in the order they were first changed.
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Load a journal, returning an empty one if the file does not exist.
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+        serde_json::from_str(&content).map_err(|e| {
+            AppError::InvalidJson(format!("Journal {} is invalid: {e}", path.display()))
+        })
+    }
+
+    /// Serialize the journal for writing.
+    pub fn to_json(&self) -> AppResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::InvalidJson(format!("Journal serialization failed: {e}")))
+    }
+
+    /// Record the original values of `changes` made to `file`.
+    ///
+    /// Returns whether anything new was recorded.
+    pub fn record(&mut self, file: &str, changes: &[FieldChange]) -> bool {
+        let mut recorded = false;
+        for change in changes {
+            let known = self
+                .entries
+                .iter()
+                .any(|entry| entry.file == file && entry.pointer == change.pointer);
+            if !known {
+                self.entries.push(JournalEntry {
+                    file: file.to_string(),
+                    pointer: change.pointer.clone(),
+                    old: change.old.clone(),
+                });
+                recorded = true;
+            }
+        }
+        recorded
+    }
+
+    /// Remove and return the entries of `file`.
+    pub fn take_file(&mut self, file: &str) -> Vec<JournalEntry> {
+        let (taken, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| entry.file == file);
+        self.entries = kept;
+        taken
+    }
+}
+
+/// Put journaled fields back to their original values.
+///
+/// Fields the tool created are removed.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::journal::{unpatch, JournalEntry};
+/// use serde_json::json;
+///
+/// let entries = vec![JournalEntry { file: "Local State".into(), pointer: "/intl/app_locale".into(), old: Some(json!("de")) }];
+/// let output = unpatch(r#"{"intl":{"app_locale":"en-US"}}"#, &entries).unwrap();
+/// assert_eq!(output, r#"{"intl":{"app_locale":"de"}}"#);
+/// ```
+pub fn unpatch(input: &str, entries: &[JournalEntry]) -> AppResult<String> {
+    let mut json: Value = serde_json::from_str(input)
+        .map_err(|e| AppError::InvalidJson(format!("Input JSON parsing failed: {e}")))?;
+    for entry in entries {
+        match &entry.old {
+            Some(old) => {
+                set_pointer(&mut json, &entry.pointer, old.clone());
+            }
+            None => {
+                remove_pointer(&mut json, &entry.pointer);
+            }
+        }
+    }
+    serde_json::to_string(&json)
+        .map_err(|e| AppError::InvalidJson(format!("Output JSON serialization failed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn change(pointer: &str, old: Option<Value>) -> FieldChange {
+        FieldChange {
+            pointer: pointer.to_string(),
+            old,
+            new: json!("en-US"),
+        }
+    }
+
+    #[test]
+    fn keeps_first_value_per_field() {
+        let mut journal = Journal::default();
+        assert!(journal.record(
+            "Local State",
+            &[change("/intl/app_locale", Some(json!("de")))]
+        ));
+        assert!(!journal.record(
+            "Local State",
+            &[change("/intl/app_locale", Some(json!("en-US")))]
+        ));
+        assert!(journal.record(
+            "Default/Preferences",
+            &[change("/intl/accept_languages", None)]
+        ));
+
+        assert_eq!(journal.entries.len(), 2);
+
+        let taken = journal.take_file("Local State");
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].old, Some(json!("de")));
+        assert_eq!(journal.entries[0].file, "Default/Preferences");
+    }
+
+    #[test]
+    fn unpatch_removes_created_fields() {
+        let entries = vec![
+            JournalEntry {
+                file: "Default/Preferences".into(),
+                pointer: "/intl/accept_languages".into(),
+                old: None,
+            },
+            JournalEntry {
+                file: "Default/Preferences".into(),
+                pointer: "/glic/launcher_enabled".into(),
+                old: Some(json!(false)),
+            },
+        ];
+        let input = r#"{"intl":{"accept_languages":"en-US,en","selected_languages":"en-US"},"glic":{"launcher_enabled":true}}"#;
+        let output: Value = serde_json::from_str(&unpatch(input, &entries).unwrap()).unwrap();
+        assert_eq!(
+            output,
+            json!({"intl": {"selected_languages": "en-US"}, "glic": {"launcher_enabled": false}})
+        );
+    }
+
+    #[test]
+    fn round_trips_through_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(JOURNAL_FILE);
+        assert_eq!(Journal::load(&path).unwrap(), Journal::default());
+
+        let mut journal = Journal::default();
+        journal.record("Local State", &[change("/intl/app_locale", None)]);
+        fs::write(&path, journal.to_json().unwrap()).unwrap();
+        assert_eq!(Journal::load(&path).unwrap(), journal);
+    }
+}
@@ -0,0 +1,245 @@
+use serde_json::{Value, json};
+
+use crate::error::{AppError, AppResult};
+use crate::patcher::PatchRule;
+
+/// Local State pointer of the chrome://flags choices.
+pub const LABS_POINTER: &str = "/browser/enabled_labs_experiments";
+
+/// Options of a plain feature flag, in chrome://flags order.
+const FEATURE_OPTIONS: &[&str] = &["Default", "Enabled", "Disabled"];
+
+/// A chrome://flags entry related to Gemini.
+///
+/// # Examples
+///
+/// ```text
+/// LabsFlag { name: "glic", description: "Gemini in Chrome", options: &["Default", "Enabled", "Disabled"] }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LabsFlag {
+    /// Internal name, as in `chrome://flags/#glic`.
+    pub name: &'static str,
+    /// What the flag controls.
+    pub description: &'static str,
+    /// Option labels; the index of an option is what Chrome stores after `@`.
+    pub options: &'static [&'static str],
+}
+
+impl LabsFlag {
+    /// Index of the "Disabled" option, if the flag has one.
+    pub fn disabled_index(&self) -> Option<usize> {
+        self.options.iter().position(|option| *option == "Disabled")
+    }
+}
+
+/// Curated chrome://flags entries users are told to set for Gemini.
+pub const LABS_CATALOG: &[LabsFlag] = &[
+    LabsFlag {
+        name: "glic",
+        description: "Gemini in Chrome",
+        options: FEATURE_OPTIONS,
+    },
+    LabsFlag {
+        name: "glic-rollout",
+        description: "Gemini in Chrome rollout to more users",
+        options: FEATURE_OPTIONS,
+    },
+    LabsFlag {
+        name: "glic-actor",
+        description: "Let Gemini act on web pages",
+        options: FEATURE_OPTIONS,
+    },
+    LabsFlag {
+        name: "optimization-guide-on-device-model",
+        description: "On-device model used by the built-in AI APIs",
+        options: &["Default", "Enabled", "Enabled BypassPerfRequirement", "Disabled"],
+    },
+    LabsFlag {
+        name: "prompt-api-for-gemini-nano",
+        description: "Prompt API for Gemini Nano",
+        options: &["Default", "Enabled", "Enabled multilingual", "Disabled"],
+    },
+    LabsFlag {
+        name: "summarization-api-for-gemini-nano",
+        description: "Summarization API for Gemini Nano",
+        options: &["Default", "Enabled", "Enabled multilingual", "Disabled"],
+    },
+];
+
+/// Look a flag up in [`LABS_CATALOG`].
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::labs::catalog_flag;
+///
+/// assert_eq!(catalog_flag("glic").unwrap().options[1], "Enabled");
+/// assert!(catalog_flag("smooth-scrolling").is_err());
+/// ```
+pub fn catalog_flag(name: &str) -> AppResult<&'static LabsFlag> {
+    LABS_CATALOG
+        .iter()
+        .find(|flag| flag.name == name)
+        .ok_or_else(|| AppError::UnknownFlag(name.to_string()))
+}
+
+/// What to do with a flag.
+///
+/// # Examples
+///
+/// ```text
+/// FlagChoice::Enable(2)
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FlagChoice {
+    /// Select the option at this index (1 is "Enabled").
+    Enable(usize),
+    /// Select the "Disabled" option.
+    Disable,
+    /// Remove the entry so Chrome uses the default.
+    Reset,
+}
+
+/// Entries of `browser.enabled_labs_experiments`, e.g. `glic@1`.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::labs::labs_entries;
+/// use serde_json::json;
+///
+/// let local_state = json!({"browser": {"enabled_labs_experiments": ["glic@1"]}});
+/// assert_eq!(labs_entries(&local_state), ["glic@1"]);
+/// ```
+pub fn labs_entries(local_state: &Value) -> Vec<String> {
+    local_state
+        .pointer(LABS_POINTER)
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Split an entry into its flag name and option index.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::labs::parse_entry;
+///
+/// assert_eq!(parse_entry("glic@1"), ("glic", Some(1)));
+/// assert_eq!(parse_entry("enable-foo"), ("enable-foo", None));
+/// ```
+pub fn parse_entry(entry: &str) -> (&str, Option<usize>) {
+    match entry.rsplit_once('@') {
+        Some((name, index)) => match index.parse() {
+            Ok(index) => (name, Some(index)),
+            Err(_) => (entry, None),
+        },
+        None => (entry, None),
+    }
+}
+
+/// Rule writing the flag entries with `choice` applied to `name`.
+///
+/// Entries of other flags, including ones outside the catalog, are kept in
+/// their order; the changed flag's entry is replaced in place or appended.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::labs::{flag_rule, FlagChoice};
+/// use serde_json::json;
+///
+/// let local_state = json!({"browser": {"enabled_labs_experiments": ["glic@2"]}});
+/// let rule = flag_rule(&local_state, "glic", FlagChoice::Enable(1)).unwrap();
+/// assert_eq!(rule.value, json!(["glic@1"]));
+/// ```
+pub fn flag_rule(local_state: &Value, name: &str, choice: FlagChoice) -> AppResult<PatchRule> {
+    let flag = catalog_flag(name)?;
+    let index = match choice {
+        FlagChoice::Enable(index) if index == 0 || index >= flag.options.len() => {
+            return Err(AppError::InvalidFlagOption {
+                name: name.to_string(),
+                index,
+                options: flag.options.len() - 1,
+            });
+        }
+        FlagChoice::Enable(index) => Some(index),
+        FlagChoice::Disable => Some(flag.disabled_index().ok_or_else(|| {
+            AppError::InvalidFlagOption {
+                name: name.to_string(),
+                index: 0,
+                options: flag.options.len() - 1,
+            }
+        })?),
+        FlagChoice::Reset => None,
+    };
+
+    let mut entries = labs_entries(local_state);
+    let position = entries.iter().position(|entry| parse_entry(entry).0 == name);
+    match (position, index) {
+        (Some(position), Some(index)) => entries[position] = format!("{name}@{index}"),
+        (None, Some(index)) => entries.push(format!("{name}@{index}")),
+        (Some(position), None) => {
+            entries.remove(position);
+        }
+        (None, None) => {}
+    }
+    Ok(PatchRule::set(LABS_POINTER, json!(entries)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_foreign_entries() {
+        let local_state = json!({"browser": {"enabled_labs_experiments": [
+            "smooth-scrolling@2", "glic@1", "enable-foo"
+        ]}});
+        let rule = flag_rule(&local_state, "glic", FlagChoice::Disable).unwrap();
+        assert_eq!(rule.value, json!(["smooth-scrolling@2", "glic@2", "enable-foo"]));
+
+        let rule = flag_rule(&local_state, "glic", FlagChoice::Reset).unwrap();
+        assert_eq!(rule.value, json!(["smooth-scrolling@2", "enable-foo"]));
+
+        let rule = flag_rule(&local_state, "glic-actor", FlagChoice::Enable(1)).unwrap();
+        assert_eq!(
+            rule.value,
+            json!(["smooth-scrolling@2", "glic@1", "enable-foo", "glic-actor@1"])
+        );
+    }
+
+    #[test]
+    fn creates_list_when_missing() {
+        let rule = flag_rule(
+            &json!({}),
+            "optimization-guide-on-device-model",
+            FlagChoice::Enable(2),
+        )
+        .unwrap();
+        assert_eq!(rule.value, json!(["optimization-guide-on-device-model@2"]));
+        assert!(rule.create);
+    }
+
+    #[test]
+    fn rejects_unknown_flags_and_options() {
+        assert!(matches!(
+            flag_rule(&json!({}), "glic-nope", FlagChoice::Enable(1)),
+            Err(AppError::UnknownFlag(_))
+        ));
+        for index in [0, 3] {
+            assert!(matches!(
+                flag_rule(&json!({}), "glic", FlagChoice::Enable(index)),
+                Err(AppError::InvalidFlagOption { .. })
+            ));
+        }
+    }
+}
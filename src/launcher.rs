@@ -0,0 +1,363 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+use crate::files::GENERATED_MARKER;
+use crate::process::ChromeChannel;
+
+/// System directories holding the browsers' own `.desktop` files.
+pub const SYSTEM_APPLICATION_DIRS: &[&str] =
+    &["/usr/local/share/applications", "/usr/share/applications"];
+
+/// Desktop entry stem of a channel, which is also its icon name.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::launcher::desktop_stem;
+/// use gemini_unlock::process::ChromeChannel;
+///
+/// assert_eq!(desktop_stem(ChromeChannel::Dev), "google-chrome-unstable");
+/// ```
+pub fn desktop_stem(channel: ChromeChannel) -> &'static str {
+    match channel {
+        ChromeChannel::Stable | ChromeChannel::Unknown => "google-chrome",
+        ChromeChannel::Beta => "google-chrome-beta",
+        ChromeChannel::Dev => "google-chrome-unstable",
+        ChromeChannel::Canary => "google-chrome-canary",
+        ChromeChannel::Chromium => "chromium",
+    }
+}
+
+/// Where a channel's browser binary may be installed, most specific first.
+pub fn binary_candidates(channel: ChromeChannel) -> &'static [&'static str] {
+    match channel {
+        ChromeChannel::Stable | ChromeChannel::Unknown => &[
+            "/usr/bin/google-chrome-stable",
+            "/usr/bin/google-chrome",
+            "/opt/google/chrome/google-chrome",
+        ],
+        ChromeChannel::Beta => &[
+            "/usr/bin/google-chrome-beta",
+            "/opt/google/chrome-beta/google-chrome-beta",
+        ],
+        ChromeChannel::Dev => &[
+            "/usr/bin/google-chrome-unstable",
+            "/opt/google/chrome-unstable/google-chrome-unstable",
+        ],
+        ChromeChannel::Canary => &[
+            "/usr/bin/google-chrome-canary",
+            "/opt/google/chrome-canary/google-chrome-canary",
+        ],
+        ChromeChannel::Chromium => &[
+            "/usr/bin/chromium",
+            "/usr/bin/chromium-browser",
+            "/snap/bin/chromium",
+        ],
+    }
+}
+
+/// First installed binary of `channel`, with candidates resolved below `root`.
+///
+/// # Examples
+///
+/// ```text
+/// let binary = find_chrome_binary(ChromeChannel::Stable, Path::new("/"));
+/// ```
+pub fn find_chrome_binary(channel: ChromeChannel, root: &Path) -> Option<PathBuf> {
+    binary_candidates(channel)
+        .iter()
+        .map(|candidate| root.join(candidate.trim_start_matches('/')))
+        .find(|path| path.is_file())
+}
+
+/// Validate a variations country code and lower-case it.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::launcher::normalize_country;
+///
+/// assert_eq!(normalize_country("US").unwrap(), "us");
+/// assert!(normalize_country("usa").is_err());
+/// ```
+pub fn normalize_country(country: &str) -> AppResult<String> {
+    let country = country.trim();
+    if country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(country.to_ascii_lowercase())
+    } else {
+        Err(AppError::InvalidCountry(country.to_string()))
+    }
+}
+
+/// Chrome flags overriding the variations country and enabling `features`.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::launcher::launch_flags;
+///
+/// let flags = launch_flags("us", &["Glic".into(), "GlicRollout".into()]).unwrap();
+/// assert_eq!(flags, ["--variations-override-country=us", "--enable-features=Glic,GlicRollout"]);
+/// ```
+pub fn launch_flags(country: &str, features: &[String]) -> AppResult<Vec<String>> {
+    let mut flags = vec![format!(
+        "--variations-override-country={}",
+        normalize_country(country)?
+    )];
+    for feature in features {
+        let valid = !feature.is_empty()
+            && feature
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-.:/<>".contains(c));
+        if !valid {
+            return Err(AppError::InvalidFeature(feature.clone()));
+        }
+    }
+    if !features.is_empty() {
+        flags.push(format!("--enable-features={}", features.join(",")));
+    }
+    Ok(flags)
+}
+
+/// Files written by `launcher` for one channel.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::launcher::LauncherPaths;
+/// use gemini_unlock::process::ChromeChannel;
+///
+/// let paths = LauncherPaths::new(ChromeChannel::Stable, Path::new("/home/me/.local/share"), Path::new("/home/me/.local/bin"));
+/// assert_eq!(paths.desktop, Path::new("/home/me/.local/share/applications/google-chrome.desktop"));
+/// assert_eq!(paths.wrapper, Path::new("/home/me/.local/bin/google-chrome-gemini"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LauncherPaths {
+    /// `.desktop` override shadowing the system entry.
+    pub desktop: PathBuf,
+    /// Shell wrapper starting the browser with the flags.
+    pub wrapper: PathBuf,
+}
+
+impl LauncherPaths {
+    /// Paths below an XDG data home and a bin directory.
+    pub fn new(channel: ChromeChannel, data_home: &Path, bin_dir: &Path) -> Self {
+        let stem = desktop_stem(channel);
+        Self {
+            desktop: data_home
+                .join("applications")
+                .join(format!("{stem}.desktop")),
+            wrapper: bin_dir.join(format!("{stem}-gemini")),
+        }
+    }
+
+    /// Paths for the current user: `$XDG_DATA_HOME` (or `~/.local/share`) and `~/.local/bin`.
+    pub fn for_user(channel: ChromeChannel) -> AppResult<Self> {
+        let home = PathBuf::from(
+            std::env::var("HOME").map_err(|_| AppError::MissingEnv("HOME".to_string()))?,
+        );
+        let data_home = match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => home.join(".local").join("share"),
+        };
+        Ok(Self::new(channel, &data_home, &home.join(".local").join("bin")))
+    }
+}
+
+/// Shell wrapper that starts `binary` with `flags` and any extra arguments.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::launcher::wrapper_script;
+///
+/// let script = wrapper_script(Path::new("/usr/bin/google-chrome"), &["--variations-override-country=us".into()]);
+/// assert!(script.ends_with("exec '/usr/bin/google-chrome' '--variations-override-country=us' \"$@\"\n"));
+/// ```
+pub fn wrapper_script(binary: &Path, flags: &[String]) -> String {
+    let mut command = vec![shell_quote(&binary.to_string_lossy())];
+    command.extend(flags.iter().map(|flag| shell_quote(flag)));
+    format!(
+        "#!/bin/sh\n{GENERATED_MARKER}; remove with `gemini-unlock launcher --uninstall`\nexec {} \"$@\"\n",
+        command.join(" ")
+    )
+}
+
+/// `.desktop` override that starts `wrapper` instead of the browser.
+///
+/// With the system entry as `original`, only the program of each `Exec=`
+/// line is replaced, so actions such as "New Incognito window" keep working.
+/// Without it a minimal entry is generated.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::launcher::desktop_entry;
+/// use gemini_unlock::process::ChromeChannel;
+///
+/// let original = "[Desktop Entry]\nName=Google Chrome\nExec=/usr/bin/google-chrome-stable %U\n";
+/// let entry = desktop_entry(Some(original), ChromeChannel::Stable, Path::new("/home/me/.local/bin/google-chrome-gemini"));
+/// assert!(entry.contains("\nExec=/home/me/.local/bin/google-chrome-gemini %U\n"));
+/// ```
+pub fn desktop_entry(original: Option<&str>, channel: ChromeChannel, wrapper: &Path) -> String {
+    let program = desktop_quote(&wrapper.to_string_lossy());
+    let mut out = format!("{GENERATED_MARKER}; remove with `gemini-unlock launcher --uninstall`\n");
+    match original {
+        Some(original) => {
+            for line in original.lines() {
+                match line.strip_prefix("Exec=") {
+                    Some(exec) => {
+                        let args = exec_args(exec);
+                        out.push_str("Exec=");
+                        out.push_str(&program);
+                        if !args.is_empty() {
+                            out.push(' ');
+                            out.push_str(args);
+                        }
+                    }
+                    None => out.push_str(line),
+                }
+                out.push('\n');
+            }
+        }
+        None => {
+            let stem = desktop_stem(channel);
+            let name = match channel {
+                ChromeChannel::Stable | ChromeChannel::Unknown => "Google Chrome",
+                ChromeChannel::Beta => "Google Chrome (beta)",
+                ChromeChannel::Dev => "Google Chrome (unstable)",
+                ChromeChannel::Canary => "Google Chrome (canary)",
+                ChromeChannel::Chromium => "Chromium",
+            };
+            out.push_str(&format!(
+                "[Desktop Entry]\n\
+                 Version=1.0\n\
+                 Type=Application\n\
+                 Name={name}\n\
+                 Exec={program} %U\n\
+                 Icon={stem}\n\
+                 Terminal=false\n\
+                 StartupNotify=true\n\
+                 Categories=Network;WebBrowser;\n\
+                 MimeType=text/html;x-scheme-handler/http;x-scheme-handler/https;\n"
+            ));
+        }
+    }
+    out
+}
+
+/// Arguments of an `Exec=` value, after its (possibly quoted) program.
+fn exec_args(exec: &str) -> &str {
+    let exec = exec.trim_start();
+    let rest = match exec.strip_prefix('"') {
+        Some(quoted) => {
+            let mut escaped = false;
+            let end = quoted
+                .char_indices()
+                .find(|&(_, c)| {
+                    let close = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    close
+                })
+                .map_or(quoted.len(), |(i, _)| i + 1);
+            &quoted[end..]
+        }
+        None => exec.find(' ').map_or("", |i| &exec[i..]),
+    };
+    rest.trim_start()
+}
+
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn desktop_quote(value: &str) -> String {
+    if value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-+".contains(c))
+    {
+        return value.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::files::is_generated;
+
+    const SYSTEM_ENTRY: &str = "[Desktop Entry]\n\
+        Name=Google Chrome\n\
+        Exec=/usr/bin/google-chrome-stable %U\n\
+        Actions=new-window;new-private-window;\n\
+        \n\
+        [Desktop Action new-private-window]\n\
+        Name=New Incognito window\n\
+        Exec=\"/usr/bin/google-chrome-stable\" --incognito\n";
+
+    #[test]
+    fn rewrites_every_exec_line() {
+        let wrapper = Path::new("/home/me/My Apps/google-chrome-gemini");
+        let entry = desktop_entry(Some(SYSTEM_ENTRY), ChromeChannel::Stable, wrapper);
+        assert!(is_generated(&entry));
+        let execs: Vec<&str> = entry.lines().filter(|l| l.starts_with("Exec=")).collect();
+        assert_eq!(
+            execs,
+            [
+                "Exec=\"/home/me/My Apps/google-chrome-gemini\" %U",
+                "Exec=\"/home/me/My Apps/google-chrome-gemini\" --incognito",
+            ]
+        );
+        assert!(entry.contains("Name=New Incognito window\n"));
+        assert!(!is_generated(SYSTEM_ENTRY));
+    }
+
+    #[test]
+    fn generates_entry_without_system_file() {
+        let entry = desktop_entry(None, ChromeChannel::Beta, Path::new("/b/google-chrome-beta-gemini"));
+        assert!(entry.contains("Name=Google Chrome (beta)\n"));
+        assert!(entry.contains("Exec=/b/google-chrome-beta-gemini %U\n"));
+        assert!(entry.contains("Icon=google-chrome-beta\n"));
+    }
+
+    #[test]
+    fn wrapper_quotes_arguments() {
+        let flags = launch_flags("CA", &["Glic".into()]).unwrap();
+        let script = wrapper_script(Path::new("/opt/it's/chrome"), &flags);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(is_generated(&script));
+        assert!(script.contains(
+            "exec '/opt/it'\\''s/chrome' '--variations-override-country=ca' '--enable-features=Glic' \"$@\""
+        ));
+
+        assert_eq!(launch_flags("us", &[]).unwrap().len(), 1);
+        assert!(launch_flags("us", &["Glic Rollout".into()]).is_err());
+        assert!(launch_flags("united states", &[]).is_err());
+    }
+
+    #[test]
+    fn finds_binary_below_root() {
+        let root = tempfile::TempDir::new().unwrap();
+        assert_eq!(find_chrome_binary(ChromeChannel::Beta, root.path()), None);
+
+        let binary = root.path().join("opt/google/chrome-beta/google-chrome-beta");
+        std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        std::fs::write(&binary, "").unwrap();
+        assert_eq!(
+            find_chrome_binary(ChromeChannel::Beta, root.path()),
+            Some(binary)
+        );
+        assert_eq!("unstable".parse::<ChromeChannel>(), Ok(ChromeChannel::Dev));
+    }
+}
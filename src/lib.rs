@@ -3,6 +3,29 @@
 //! This library provides core functionality for modifying Chrome configuration
 //! to enable Gemini features.
 
+pub mod compat;
+pub mod config;
+pub mod discover;
+pub mod doctor;
 pub mod error;
+pub mod files;
+pub mod flagsconf;
+pub mod hooks;
+pub mod initialprefs;
+pub mod journal;
+pub mod labs;
+pub mod launcher;
+pub mod locale;
+pub mod manifest;
 pub mod patcher;
 pub mod platform;
+pub mod process;
+pub mod policy;
+pub mod prefhash;
+pub mod profile;
+pub mod service;
+pub mod shutdown;
+pub mod state;
+pub mod transaction;
+pub mod watch;
+pub mod workflow;
@@ -0,0 +1,145 @@
+use serde_json::json;
+
+use crate::error::{AppError, AppResult};
+use crate::patcher::PatchRule;
+
+/// Variations country and UI locale that go together for a region.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::locale::DEFAULT_REGION;
+///
+/// assert_eq!(DEFAULT_REGION.locale, "en-US");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RegionPreset {
+    /// Variations country code, e.g. `us`.
+    pub country: &'static str,
+    /// Chrome UI locale, e.g. `en-US`.
+    pub locale: &'static str,
+}
+
+/// The region Gemini is unlocked for.
+pub const DEFAULT_REGION: RegionPreset = RegionPreset {
+    country: "us",
+    locale: "en-US",
+};
+
+/// Local State pointer of Chrome's UI language.
+pub const APP_LOCALE_POINTER: &str = "/intl/app_locale";
+
+/// Preferences pointer of a profile's preferred web content languages.
+pub const ACCEPT_LANGUAGES_POINTER: &str = "/intl/accept_languages";
+
+/// Validate and normalize a BCP 47 language tag the way Chrome writes it.
+///
+/// The language is lower-cased, a script title-cased and a region
+/// upper-cased; `_` is accepted as a separator.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::locale::normalize_locale;
+///
+/// assert_eq!(normalize_locale("en_us").unwrap(), "en-US");
+/// assert_eq!(normalize_locale("zh-hant-tw").unwrap(), "zh-Hant-TW");
+/// assert!(normalize_locale("english").is_err());
+/// ```
+pub fn normalize_locale(tag: &str) -> AppResult<String> {
+    let invalid = || AppError::InvalidLocale(tag.to_string());
+    let mut subtags = tag.trim().split(['-', '_']);
+
+    let language = subtags
+        .next()
+        .filter(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()));
+    let mut parts = vec![language.ok_or_else(invalid)?.to_ascii_lowercase()];
+    for subtag in subtags {
+        if subtag.is_empty()
+            || subtag.len() > 8
+            || !subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(invalid());
+        }
+        let part = match subtag.len() {
+            2 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => subtag.to_ascii_uppercase(),
+            4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                let (first, rest) = subtag.split_at(1);
+                first.to_ascii_uppercase() + &rest.to_ascii_lowercase()
+            }
+            _ => subtag.to_ascii_lowercase(),
+        };
+        parts.push(part);
+    }
+    Ok(parts.join("-"))
+}
+
+/// Accept-Language list for a locale: the tag, then its bare language.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::locale::accept_languages;
+///
+/// assert_eq!(accept_languages("en-US"), "en-US,en");
+/// assert_eq!(accept_languages("fr"), "fr");
+/// ```
+pub fn accept_languages(locale: &str) -> String {
+    match locale.split_once('-') {
+        Some((language, _)) => format!("{locale},{language}"),
+        None => locale.to_string(),
+    }
+}
+
+/// Local State rule setting the UI locale.
+///
+/// # Examples
+///
+/// ```text
+/// apply_rules(&local_state, &locale_rules("en-US"))?
+/// ```
+pub fn locale_rules(locale: &str) -> Vec<PatchRule> {
+    vec![PatchRule::set(APP_LOCALE_POINTER, json!(locale))]
+}
+
+/// Preferences rule setting the content languages for `locale`.
+///
+/// # Examples
+///
+/// ```text
+/// rules.extend(accept_languages_rules("en-US"));
+/// ```
+pub fn accept_languages_rules(locale: &str) -> Vec<PatchRule> {
+    vec![PatchRule::set(
+        ACCEPT_LANGUAGES_POINTER,
+        json!(accept_languages(locale)),
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patcher::apply_rules;
+
+    #[test]
+    fn normalizes_tags() {
+        assert_eq!(normalize_locale("EN").unwrap(), "en");
+        assert_eq!(normalize_locale("es-419").unwrap(), "es-419");
+        assert_eq!(normalize_locale("pt_br").unwrap(), "pt-BR");
+        for bad in ["", "e", "en-", "en--US", "en-US!", "12-US"] {
+            assert!(normalize_locale(bad).is_err(), "{bad:?} accepted");
+        }
+    }
+
+    #[test]
+    fn sets_locale_and_reports_old_value() {
+        let report =
+            apply_rules(r#"{"intl":{"app_locale":"de"}}"#, &locale_rules("en-US")).unwrap();
+        assert_eq!(report.changes[0].old, Some(json!("de")));
+        assert!(report.content.contains(r#""app_locale":"en-US""#));
+
+        let report = apply_rules("{}", &accept_languages_rules("en-US")).unwrap();
+        assert_eq!(report.changes[0].old, None);
+        assert!(report.content.contains(r#""accept_languages":"en-US,en""#));
+    }
+}
@@ -1,18 +1,91 @@
 mod cli;
-mod error;
-mod patcher;
-mod platform;
 
 use anyhow::{Context, Result};
 use clap::Parser;
 use log::{error, info, warn};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::cli::Cli;
-use crate::error::AppError;
-use crate::patcher::apply_patches;
-use crate::platform::{chrome_state_path, current_os, is_chrome_running, stop_chrome};
+use serde_json::Value;
+
+use gemini_unlock::compat::{
+    compatibility, ChromeVersion, Compatibility, RuleSet, CONSISTENCY_POINTER, MIN_GEMINI_MAJOR,
+    RULE_SETS,
+};
+use gemini_unlock::config::{config_home, default_config_path, load_config, PrefHashConfig};
+use gemini_unlock::doctor::{
+    run_checks, safe_seed_rules, BackupHealth, CheckResult, CheckStatus, DoctorInput, Fix,
+    ProfileState, PROXY_ENV_VARS,
+};
+use gemini_unlock::discover::{discover_fields, suggest_rule, DISCOVERY_PATTERNS};
+use gemini_unlock::error::AppError;
+use gemini_unlock::files::{
+    backup_file, backup_path, ensure_replaceable, is_generated, is_writable, remove_generated,
+    write_atomic, Removal,
+};
+use gemini_unlock::flagsconf::{
+    block_flags, block_text, flags_conf_path, remove_block_from, set_block, BlockRemoval,
+};
+use gemini_unlock::hooks::{self, read_hook_users, write_hook_users, PackageManager, HOOK_USERS_FILE};
+use gemini_unlock::initialprefs::{default_initial_preferences_path, initial_preferences};
+use gemini_unlock::journal::{Journal, JOURNAL_FILE};
+use gemini_unlock::labs::{
+    catalog_flag, flag_rule, labs_entries, parse_entry, FlagChoice, LABS_CATALOG,
+};
+use gemini_unlock::launcher::{
+    desktop_entry, desktop_stem, find_chrome_binary, launch_flags, wrapper_script, LauncherPaths,
+    SYSTEM_APPLICATION_DIRS,
+};
+use gemini_unlock::locale::{locale_rules, DEFAULT_REGION};
+use gemini_unlock::patcher::{
+    apply_rules, mark_clean_exit, FieldOutcome, PatchReport, RuleReport,
+};
+use gemini_unlock::manifest::{
+    manifest_path, system_manifest_path, Artifact, ArtifactKind, ArtifactStatus, Manifest,
+    UninstallOutcome,
+};
+use gemini_unlock::platform::{
+    chrome_state_path, current_os, detect_chrome_version, other_chrome_processes, policy_file_path,
+    profile_in_use, read_policies, relaunch_chrome, remove_stale_singleton_lock,
+    singleton_lock_status, stop_chrome, systemctl_user, wait_for_profile_release, LockStatus,
+    OsKind,
+};
+use gemini_unlock::policy::{
+    cloud_policy_hints, gemini_findings, policy_file_content, PolicyLevel, PolicyType,
+    GEMINI_POLICY_SCHEMA,
+};
+use gemini_unlock::prefhash::{verify_macs, MacStatus, PrefHasher, SECURE_PREFERENCES_FILE};
+use gemini_unlock::process::ChromeChannel;
+use gemini_unlock::profile::{list_profiles, open_profile_dirs, PREFERENCES_FILE};
+use gemini_unlock::service::{
+    default_unit_dir, unit_files, ServiceTrigger, PATH_UNIT, SERVICE_UNIT, TIMER_UNIT, UNIT_FILES,
+};
+use gemini_unlock::state::{describe_age, unix_now, PatchState, STATE_FILE};
+use gemini_unlock::shutdown::{LaunchCommand, ShutdownPolicy, ShutdownReport};
+use gemini_unlock::transaction::{FileOutcome, Transaction, TransactionReport};
+use gemini_unlock::watch::{find_drift, FileStamp, SettleTracker};
+use gemini_unlock::workflow::{
+    apply_workflow, commit_workflow, patch_local_state, preferences_path, read_json_if_exists,
+    read_local_state, revert_journal, selected_profile, target_profile_dirs, ApplyOptions,
+    LOCAL_STATE_LABEL,
+};
+
+use crate::cli::{Cli, Commands, FlagsAction, HookAction, PolicyAction};
+
+/// Exit code when `--wait` times out before Chrome is closed.
+const EXIT_WAIT_TIMEOUT: i32 = 3;
+
+/// What to do when the target Chrome is running.
+enum BusyAction<'a> {
+    /// Fail with `ChromeRunning`.
+    Fail,
+    /// Stop Chrome with the given policy.
+    Kill(&'a ShutdownPolicy),
+    /// Wait for the user to close Chrome, optionally with a timeout.
+    Wait(Option<Duration>),
+}
 
 fn main() {
     // Initialize logging system
@@ -22,14 +95,32 @@ fn main() {
 
     if let Err(err) = run() {
         error!("Program execution failed: {err}");
-        std::process::exit(1);
+        let code = match err.downcast_ref::<AppError>() {
+            Some(AppError::WaitTimedOut(_)) => EXIT_WAIT_TIMEOUT,
+            _ => 1,
+        };
+        std::process::exit(code);
     }
 }
 
 fn run() -> Result<()> {
     let cli = Cli::parse();
     info!("Chrome Gemini patch tool started");
-    info!("Parameters: kill_chrome={}, restore={}", cli.kill_chrome, cli.restore);
+    info!(
+        "Parameters: kill_chrome={}, wait={:?}, restore={}",
+        cli.kill_chrome, cli.wait, cli.restore
+    );
+
+    let config_path = match &cli.config {
+        Some(path) => path.clone(),
+        None => default_config_path()?,
+    };
+    let config = load_config(&config_path)?;
+    let manifest_path = manifest_path(&config_path);
+    let policy_root = config.policy_root();
+    let system_manifest = system_manifest_path(&policy_root);
+    let hook_root = config.hook_root();
+    let shutdown = cli.shutdown.clone().unwrap_or(config.shutdown);
 
     let os = current_os()?;
     info!("Detected OS: {:?}", os);
@@ -37,57 +128,1507 @@ fn run() -> Result<()> {
     let chrome_state = chrome_state_path(os)?;
     info!("Chrome config path: {}", chrome_state.display());
 
-    let backup_path = create_backup_path(&chrome_state)?;
+    let user_data_dir = chrome_state
+        .parent()
+        .ok_or_else(|| AppError::InvalidPath(chrome_state.clone()))?;
+
+    let mut recorded = None;
+    let mut labs_change = None;
+    match &cli.command {
+        Some(Commands::Profiles) => return show_profiles(&chrome_state),
+        Some(Commands::Doctor { fix }) => {
+            let target = DoctorTarget {
+                os,
+                chrome_version: detect_chrome_version(os, user_data_dir),
+                chrome_state: &chrome_state,
+                user_data_dir,
+                policy_root: &policy_root,
+                profile: cli.profile.as_deref(),
+                pref_hash: &config.pref_hash,
+            };
+            return run_doctor(&target, *fix);
+        }
+        Some(Commands::VerifyMacs { seed, device_id }) => {
+            let hasher = PrefHasher::from_hex_seed(
+                seed.as_deref().unwrap_or(&config.pref_hash.seed),
+                device_id.as_deref().unwrap_or(&config.pref_hash.device_id),
+            )?;
+            return verify_profile_macs(&chrome_state, user_data_dir, cli.profile.as_deref(), &hasher);
+        }
+        Some(Commands::Policy { action }) => {
+            return match action {
+                PolicyAction::Install { target, set } => {
+                    let path = policy_file_path(os, &policy_root, target.level, target.chromium)?;
+                    install_policy(&path, set, &system_manifest)
+                }
+                PolicyAction::Remove { target } => {
+                    let path = policy_file_path(os, &policy_root, target.level, target.chromium)?;
+                    remove_policy(&path, &system_manifest)
+                }
+                PolicyAction::Schema => {
+                    show_policy_schema();
+                    Ok(())
+                }
+            };
+        }
+        Some(Commands::Launcher {
+            channel,
+            country,
+            features,
+            uninstall,
+        }) => {
+            if os != OsKind::Linux {
+                return Err(AppError::UnsupportedOs(format!("{os:?} has no .desktop launchers")).into());
+            }
+            let paths = LauncherPaths::for_user(*channel)?;
+            if *uninstall {
+                return uninstall_launcher(&paths, &manifest_path);
+            }
+            let flags = launch_flags(
+                country.as_deref().unwrap_or(&config.launcher.country),
+                features.as_ref().unwrap_or(&config.launcher.features),
+            )?;
+            return install_launcher(*channel, &paths, &flags, &manifest_path);
+        }
+        Some(Commands::FlagsConf {
+            channel,
+            country,
+            features,
+            remove,
+        }) => {
+            let path = flags_conf_path(*channel, &config_home()?);
+            if *remove {
+                return remove_flags_conf(&path, &manifest_path);
+            }
+            let flags = launch_flags(
+                country.as_deref().unwrap_or(&config.launcher.country),
+                features.as_ref().unwrap_or(&config.launcher.features),
+            )?;
+            return update_flags_conf(&path, &flags, &manifest_path);
+        }
+        Some(Commands::Flags { action }) => {
+            let (name, choice) = match action {
+                FlagsAction::List => return show_labs_flags(&chrome_state),
+                FlagsAction::Enable { name, variant } => (name, FlagChoice::Enable(*variant)),
+                FlagsAction::Disable { name } => (name, FlagChoice::Disable),
+                FlagsAction::Reset { name } => (name, FlagChoice::Reset),
+            };
+            catalog_flag(name)?;
+            labs_change = Some((name, choice));
+        }
+        Some(Commands::InitialPrefs {
+            path,
+            locale,
+            skip_first_run,
+        }) => {
+            let path = path
+                .clone()
+                .or_else(|| config.initial_preferences.clone())
+                .unwrap_or_else(|| default_initial_preferences_path(os));
+            let locale = locale.as_deref().unwrap_or(DEFAULT_REGION.locale);
+            return write_initial_preferences(&path, locale, *skip_first_run, &system_manifest);
+        }
+        Some(Commands::Watch { interval, settle, once }) => {
+            let chrome_version = detect_chrome_version(os, user_data_dir);
+            let rule_set = select_rule_set(chrome_version)?;
+            let options = ApplyOptions {
+                chrome_version,
+                rule_set,
+                profile: cli.profile.as_deref(),
+                locale: cli.locale.as_deref(),
+                accept_languages: cli.accept_languages,
+                sync_safe_seed: false,
+                dry_run: false,
+                pref_hash: &config.pref_hash,
+                backed_up: &[],
+            };
+            return watch_local_state(os, &chrome_state, user_data_dir, &options, *interval, *settle, *once);
+        }
+        Some(Commands::InstallService { timer, target }) => {
+            if os != OsKind::Linux {
+                return Err(AppError::UnsupportedOs(format!("{os:?} has no systemd user units")).into());
+            }
+            let mut command = vec![
+                std::env::current_exe()?.display().to_string(),
+                "--config".to_string(),
+                std::path::absolute(&config_path)?.display().to_string(),
+            ];
+            if let Some(profile) = &cli.profile {
+                command.extend(["--profile".to_string(), profile.clone()]);
+            }
+            if let Some(locale) = &cli.locale {
+                command.extend(["--locale".to_string(), locale.clone()]);
+                if cli.accept_languages {
+                    command.push("--accept-languages".to_string());
+                }
+            }
+            command.extend(["watch".to_string(), "--once".to_string()]);
+            let trigger = timer.map_or(ServiceTrigger::Path, ServiceTrigger::Timer);
+            let unit_dir = target.unit_dir.clone().map_or_else(default_unit_dir, Ok)?;
+            let files = unit_files(&command, trigger, &chrome_state);
+            return install_service(&unit_dir, &files, trigger, target.unit_dir.is_none(), &manifest_path);
+        }
+        Some(Commands::UninstallService { target }) => {
+            let unit_dir = target.unit_dir.clone().map_or_else(default_unit_dir, Ok)?;
+            return uninstall_service(&unit_dir, target.unit_dir.is_none(), &manifest_path);
+        }
+        Some(Commands::Hook { action }) => {
+            if os != OsKind::Linux {
+                return Err(AppError::UnsupportedOs(format!("{os:?} has no package-manager hooks")).into());
+            }
+            return match action {
+                HookAction::Install { manager, users } => {
+                    let manager = match manager {
+                        Some(manager) => *manager,
+                        None => PackageManager::detect(&hook_root)?,
+                    };
+                    let mut users = users.clone();
+                    if users.is_empty()
+                        && let Ok(sudo_user) = std::env::var("SUDO_USER")
+                    {
+                        users.push(sudo_user);
+                    }
+                    install_hook(&hook_root, manager, &users, &system_manifest)
+                }
+                HookAction::Remove { users } => remove_hook(&hook_root, users, &system_manifest),
+                HookAction::Run => run_hook(&hook_root),
+            };
+        }
+        Some(Commands::Status) => return show_status(os, &chrome_state, user_data_dir),
+        Some(Commands::DiscoverFields { file, patterns, suggest }) => {
+            let path = file.as_deref().unwrap_or(&chrome_state);
+            return discover_local_state_fields(path, patterns, *suggest);
+        }
+        Some(Commands::Audit) => return audit_installed(&[&manifest_path, &system_manifest]),
+        Some(Commands::Uninstall { force }) => return uninstall(&manifest_path, &system_manifest, *force),
+        Some(Commands::Reapply) => {
+            recorded = PatchState::load(&user_data_dir.join(STATE_FILE))?;
+            if recorded.is_none() {
+                println!("• No earlier patch recorded, applying with the given options");
+            }
+        }
+        None => {}
+    }
+
+    let backup_path = backup_path(&chrome_state)?;
     info!("Backup path: {}", backup_path.display());
 
     // Ensure Chrome is not running
-    ensure_chrome_not_running(os, cli.kill_chrome)?;
+    check_singleton_lock(os, user_data_dir, cli.remove_stale_lock)?;
+    let busy_action = match cli.wait {
+        Some(timeout) => BusyAction::Wait(timeout),
+        None if cli.kill_chrome => BusyAction::Kill(&shutdown),
+        None => BusyAction::Fail,
+    };
+    let shutdown_report = ensure_chrome_not_running(os, user_data_dir, busy_action)?;
+    let mut backed_up = Vec::new();
+    if shutdown_report.as_ref().is_some_and(ShutdownReport::forced) {
+        // A restore overwrites Preferences from the existing backups, which must survive
+        match mark_profiles_clean_exit(&chrome_state, user_data_dir, cli.profile.as_deref(), !cli.restore) {
+            Ok(paths) => backed_up = paths,
+            Err(err) => warn!("Failed to mark profiles as cleanly exited: {err:#}"),
+        }
+    }
+
+    // Execute restore or apply patches; `reapply` falls back to the recorded options
+    let recorded = recorded.as_ref();
+    let profile = cli.profile.as_deref().or(recorded.and_then(|state| state.profile.as_deref()));
+    let locale = cli.locale.as_deref().or(recorded.and_then(|state| state.locale.as_deref()));
+    let accept_languages = cli.accept_languages || recorded.is_some_and(|state| state.accept_languages);
+    let result = if let Some((name, choice)) = labs_change {
+        set_labs_flag(&chrome_state, user_data_dir, name, choice, cli.dry_run)
+    } else if cli.restore {
+        restore_from_backup(&backup_path, &chrome_state)
+            .and_then(|()| restore_profiles(&chrome_state, user_data_dir, profile))
+            .and_then(|()| revert_and_report(&chrome_state, user_data_dir, profile, &config.pref_hash))
+            .and_then(|()| forget_patch_state(user_data_dir))
+    } else {
+        let chrome_version = detect_chrome_version(os, user_data_dir);
+        let rule_set = select_rule_set(chrome_version)?;
+        let options = ApplyOptions {
+            chrome_version,
+            rule_set,
+            profile,
+            locale,
+            accept_languages,
+            sync_safe_seed: false,
+            dry_run: cli.dry_run,
+            pref_hash: &config.pref_hash,
+            backed_up: &backed_up,
+        };
+        apply_and_report(&chrome_state, user_data_dir, &options).map(|()| {
+            report_policies(os, &policy_root, &chrome_state);
+            print_done(cli.dry_run)
+        })
+    };
+
+    // Relaunch even if patching failed, so the user is not left without a browser
+    if cli.relaunch {
+        relaunch(shutdown_report.and_then(|report| report.relaunch));
+    }
+
+    result
+}
+
+/// Write the Gemini policy file and record it in the manifest
+fn install_policy(path: &Path, overrides: &[(String, Value)], manifest_path: &Path) -> Result<()> {
+    let content = policy_file_content(overrides)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Create failed: {}", dir.display()))?;
+    }
+    write_atomic(path, &content).with_context(|| format!("Write failed: {}", path.display()))?;
+    info!("Policy file written: {}", path.display());
+
+    let mut manifest = Manifest::load(manifest_path)?;
+    manifest.record(Artifact::new(ArtifactKind::PolicyFile, path, &content));
+    manifest.save(manifest_path)?;
+
+    println!("✅ Policy file written: {}", path.display());
+    println!("   Restart Chrome and check chrome://policy to confirm it is applied");
+    Ok(())
+}
+
+/// Remove the Gemini policy file and its manifest record
+fn remove_policy(path: &Path, manifest_path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => {
+            info!("Policy file removed: {}", path.display());
+            println!("✅ Policy file removed: {}", path.display());
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            println!("No policy file at {}", path.display());
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("Remove failed: {}", path.display()));
+        }
+    }
+
+    let mut manifest = Manifest::load(manifest_path)?;
+    if manifest.forget(path).is_some() {
+        manifest.save(manifest_path)?;
+    }
+    Ok(())
+}
+
+/// Write the launcher wrapper and .desktop override and record them in the manifest
+fn install_launcher(
+    channel: ChromeChannel,
+    paths: &LauncherPaths,
+    flags: &[String],
+    manifest_path: &Path,
+) -> Result<()> {
+    let binary = find_chrome_binary(channel, Path::new("/"))
+        .ok_or_else(|| AppError::BrowserNotFound(desktop_stem(channel).to_string()))?;
+    info!("Chrome binary: {}", binary.display());
+
+    let desktop_name = format!("{}.desktop", desktop_stem(channel));
+    let original = SYSTEM_APPLICATION_DIRS
+        .iter()
+        .find_map(|dir| fs::read_to_string(Path::new(dir).join(&desktop_name)).ok());
+    if original.is_none() {
+        warn!("No system {desktop_name} found, generating a minimal entry");
+    }
+
+    let wrapper = wrapper_script(&binary, flags);
+    let desktop = desktop_entry(original.as_deref(), channel, &paths.wrapper);
+    for path in [&paths.wrapper, &paths.desktop] {
+        ensure_replaceable(path)?;
+    }
+
+    let mut manifest = Manifest::load(manifest_path)?;
+    for (path, content, kind) in [
+        (&paths.wrapper, &wrapper, ArtifactKind::LauncherScript),
+        (&paths.desktop, &desktop, ArtifactKind::DesktopEntry),
+    ] {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Create failed: {}", dir.display()))?;
+        }
+        write_atomic(path, content).with_context(|| format!("Write failed: {}", path.display()))?;
+        manifest.record(Artifact::new(kind, path, content));
+        println!("✅ {kind} written: {}", path.display());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&paths.wrapper, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("chmod failed: {}", paths.wrapper.display()))?;
+    }
+    manifest.save(manifest_path)?;
+
+    println!("   Chrome will start with: {}", flags.join(" "));
+    println!("   Restart Chrome from the application menu for the flags to apply");
+    Ok(())
+}
+
+/// Remove the launcher files of a channel, leaving files the tool did not write alone
+fn uninstall_launcher(paths: &LauncherPaths, manifest_path: &Path) -> Result<()> {
+    let mut manifest = Manifest::load(manifest_path)?;
+    for path in [&paths.desktop, &paths.wrapper] {
+        if remove_own_file(path, "launcher file")? == Removal::Missing {
+            println!("• {} does not exist", path.display());
+        }
+        manifest.forget(path);
+    }
+    manifest.save(manifest_path)?;
+    Ok(())
+}
+
+/// Add or update the tool's block in a flags file and record it in the manifest
+fn update_flags_conf(path: &Path, flags: &[String], manifest_path: &Path) -> Result<()> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("Read failed: {}", path.display())),
+    };
+    let updated = set_block(&content, flags)?;
+    if updated == content {
+        println!("✓ {} is already up to date", path.display());
+    } else {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Create failed: {}", dir.display()))?;
+        }
+        write_atomic(path, &updated).with_context(|| format!("Write failed: {}", path.display()))?;
+        info!("Flags block written to {}", path.display());
+        println!("✅ Flags block written to {}", path.display());
+    }
+
+    let block = block_text(&updated)?.unwrap_or_default();
+    let mut manifest = Manifest::load(manifest_path)?;
+    manifest.record(Artifact::new(ArtifactKind::FlagsBlock, path, &block));
+    manifest.save(manifest_path)?;
+    println!("   Chrome will start with: {}", flags.join(" "));
+    Ok(())
+}
+
+/// Remove the tool's block from a flags file, keeping the user's lines
+fn remove_flags_conf(path: &Path, manifest_path: &Path) -> Result<()> {
+    match remove_block_from(path).with_context(|| format!("Remove failed: {}", path.display()))? {
+        BlockRemoval::FileRemoved => println!("✅ Removed {} (it only held the flags block)", path.display()),
+        BlockRemoval::BlockRemoved => println!("✅ Flags block removed from {}", path.display()),
+        BlockRemoval::NoBlock => println!("No flags block in {}", path.display()),
+    }
+
+    let mut manifest = Manifest::load(manifest_path)?;
+    if manifest.forget(path).is_some() {
+        manifest.save(manifest_path)?;
+    }
+    Ok(())
+}
+
+/// List the catalog flags with the option currently selected in Local State
+fn show_labs_flags(chrome_state: &Path) -> Result<()> {
+    let local_state: Value = serde_json::from_str(&read_local_state(chrome_state)?)
+        .map_err(|e| AppError::InvalidJson(format!("Local State parsing failed: {e}")))?;
+    let entries = labs_entries(&local_state);
+    let width = LABS_CATALOG.iter().map(|flag| flag.name.len()).max().unwrap_or(0);
+    for flag in LABS_CATALOG {
+        let current = entries
+            .iter()
+            .map(|entry| parse_entry(entry))
+            .find(|(name, _)| *name == flag.name)
+            .and_then(|(_, index)| flag.options.get(index.unwrap_or(0)))
+            .copied()
+            .unwrap_or("Default");
+        println!("{:width$}  {current:30}  {}", flag.name, flag.description);
+        if flag.options.len() > 3 {
+            let options: Vec<String> = flag.options[1..]
+                .iter()
+                .enumerate()
+                .map(|(i, option)| format!("{}={option}", i + 1))
+                .collect();
+            println!("{:width$}  options: {}", "", options.join(", "));
+        }
+    }
+    let others = entries
+        .iter()
+        .filter(|entry| catalog_flag(parse_entry(entry).0).is_err())
+        .count();
+    if others > 0 {
+        println!();
+        println!("{others} other flag(s) set in chrome://flags are left untouched");
+    }
+    Ok(())
+}
+
+/// Set one chrome://flags entry, with a backup and an unpatch journal record
+fn set_labs_flag(
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    name: &str,
+    choice: FlagChoice,
+    dry_run: bool,
+) -> Result<()> {
+    let content = read_local_state(chrome_state)?;
+    let local_state: Value = serde_json::from_str(&content)
+        .map_err(|e| AppError::InvalidJson(format!("Local State parsing failed: {e}")))?;
+    let report = apply_rules(&content, &[flag_rule(&local_state, name, choice)?])?;
+    if report.changes.is_empty() {
+        println!("✓ {name} is already set");
+        return Ok(());
+    }
+    print_rule_report(LOCAL_STATE_LABEL, &report);
+    if dry_run {
+        print_done(true);
+        return Ok(());
+    }
 
-    // Execute restore or apply patches
-    if cli.restore {
-        restore_from_backup(&backup_path, &chrome_state)?;
+    let journal_path = user_data_dir.join(JOURNAL_FILE);
+    let mut journal = Journal::load(&journal_path)?;
+    let mut transaction = Transaction::new();
+    transaction
+        .stage_json(LOCAL_STATE_LABEL, chrome_state, &report.content)
+        .with_context(|| format!("Staging failed: {}", chrome_state.display()))?;
+    if journal.record(LOCAL_STATE_LABEL, &report.changes) {
+        transaction
+            .stage_json("Unpatch journal", &journal_path, &journal.to_json()?)
+            .with_context(|| format!("Staging failed: {}", journal_path.display()))?;
+    }
+    finish_transaction(commit_workflow(transaction, &[])?)
+}
+
+/// Write initial_preferences, merging into an existing file after backing it up
+///
+/// Only a file the tool created is recorded for `uninstall`; one shipped by
+/// the distribution keeps its `.bak` instead.
+fn write_initial_preferences(
+    path: &Path,
+    locale: &str,
+    skip_first_run: bool,
+    manifest_path: &Path,
+) -> Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(content) => Some(content),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err).with_context(|| format!("Read failed: {}", path.display())),
+    };
+    let report = initial_preferences(existing.as_deref(), locale, skip_first_run)?;
+    if existing.is_some() && report.changes.is_empty() {
+        println!("✓ {} is already up to date", path.display());
+        return Ok(());
+    }
+    print_rule_report("initial_preferences", &report);
+
+    let mut manifest = Manifest::load(manifest_path)?;
+    let ours = existing.is_none() || manifest.artifacts.iter().any(|a| a.path == path);
+    if !ours {
+        let backup = backup_file(path).with_context(|| format!("Backup failed: {}", path.display()))?;
+        info!("Backed up {} to {}", path.display(), backup.display());
+    }
+    let json: Value = serde_json::from_str(&report.content)
+        .map_err(|e| AppError::InvalidJson(format!("initial_preferences parsing failed: {e}")))?;
+    let content = serde_json::to_string_pretty(&json)
+        .map_err(|e| AppError::InvalidJson(format!("initial_preferences serialization failed: {e}")))?
+        + "\n";
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Create failed: {}", dir.display()))?;
+    }
+    write_atomic(path, &content).with_context(|| format!("Write failed: {}", path.display()))?;
+    if ours {
+        manifest.record(Artifact::new(ArtifactKind::InitialPreferences, path, &content));
+        manifest.save(manifest_path)?;
+    }
+
+    println!("✅ Initial preferences written: {}", path.display());
+    println!("   They apply to the first profile of a fresh install; Local State fields such as");
+    println!("   variations_country still need `gemini-unlock` after the first run, or `launcher`");
+    Ok(())
+}
+
+/// Poll Local State and re-apply the patch each time Chrome reverts a field
+///
+/// Runs until interrupted, or with `once` until the first settled check. A
+/// settled change is only checked while Chrome is closed, since Chrome
+/// overwrites Local State on exit.
+fn watch_local_state(
+    os: OsKind,
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    options: &ApplyOptions,
+    interval: Duration,
+    settle: Duration,
+    once: bool,
+) -> Result<()> {
+    if !once {
+        println!("👀 Watching {} (Ctrl-C to stop)", chrome_state.display());
+    }
+    info!("Watching every {interval:?}, settle time {settle:?}");
+
+    let mut tracker = SettleTracker::new(settle);
+    loop {
+        let now = Instant::now();
+        let stamp = FileStamp::of(chrome_state);
+        if once && stamp.is_none() {
+            info!("{} does not exist, nothing to check", chrome_state.display());
+            return Ok(());
+        }
+        if tracker.observe(stamp, now) {
+            if profile_in_use(os, user_data_dir)? {
+                if once {
+                    info!("Chrome is running, leaving Local State to the check after it exits");
+                    return Ok(());
+                }
+                info!("Local State changed while Chrome is running, checking again after it exits");
+                tracker.postpone(now);
+            } else if once {
+                return reapply_drift(os, chrome_state, user_data_dir, options);
+            } else if let Err(err) = reapply_drift(os, chrome_state, user_data_dir, options) {
+                error!("Re-applying the patch failed: {err:#}");
+            }
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Log fields Chrome reverted and re-apply the patch through the normal workflow
+///
+/// The rule set is picked again each time, since Chrome may have been updated
+/// while watching.
+fn reapply_drift(
+    os: OsKind,
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    options: &ApplyOptions,
+) -> Result<()> {
+    let chrome_version = detect_chrome_version(os, user_data_dir);
+    let rule_set = select_rule_set(chrome_version)?;
+    let options = ApplyOptions {
+        chrome_version,
+        rule_set,
+        ..*options
+    };
+    let mut rules = rule_set.local_state_rules(chrome_version.as_ref());
+    if let Some(locale) = options.locale {
+        rules.extend(locale_rules(locale));
+    }
+    let local_state: Value = serde_json::from_str(&read_local_state(chrome_state)?)
+        .map_err(|e| AppError::InvalidJson(format!("Local State parsing failed: {e}")))?;
+    let drift = find_drift(&local_state, &rules);
+    if drift.is_empty() {
+        info!("Local State changed, patch still applied");
+        return Ok(());
+    }
+
+    for field in &drift {
+        let actual = field
+            .actual
+            .as_ref()
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "(removed)".to_string());
+        println!("↩️ Chrome changed {} to {actual}", field.pointer);
+        warn!(
+            "Drift: Chrome changed {} to {actual} (patched value {})",
+            field.pointer, field.expected
+        );
+    }
+    apply_and_report(chrome_state, user_data_dir, &options)?;
+    println!("✅ Patch re-applied");
+    Ok(())
+}
+
+/// Write the service and trigger units, record them and enable the trigger
+fn install_service(
+    unit_dir: &Path,
+    files: &[(&str, String)],
+    trigger: ServiceTrigger,
+    systemctl: bool,
+    manifest_path: &Path,
+) -> Result<()> {
+    for (name, _) in files {
+        ensure_replaceable(&unit_dir.join(name))?;
+    }
+
+    // Switching between path and timer: the other trigger must not stay enabled.
+    let other = if trigger.unit() == PATH_UNIT { TIMER_UNIT } else { PATH_UNIT };
+    let mut manifest = Manifest::load(manifest_path)?;
+    let other_path = unit_dir.join(other);
+    if let Ok(existing) = fs::read_to_string(&other_path)
+        && is_generated(&existing)
+    {
+        if systemctl {
+            disable_units(&[other]);
+        }
+        fs::remove_file(&other_path).with_context(|| format!("Remove failed: {}", other_path.display()))?;
+        manifest.forget(&other_path);
+        println!("✅ Removed {}", other_path.display());
+    }
+
+    fs::create_dir_all(unit_dir).with_context(|| format!("Create failed: {}", unit_dir.display()))?;
+    for (name, content) in files {
+        let path = unit_dir.join(name);
+        write_atomic(&path, content).with_context(|| format!("Write failed: {}", path.display()))?;
+        manifest.record(Artifact::new(ArtifactKind::SystemdUnit, &path, content));
+        println!("✅ Unit written: {}", path.display());
+    }
+    manifest.save(manifest_path)?;
+
+    if !systemctl {
+        println!("   Enable it with: systemctl --user daemon-reload && systemctl --user enable --now {}", trigger.unit());
+        return Ok(());
+    }
+    systemctl_user(&["daemon-reload"])?;
+    systemctl_user(&["enable", "--now", trigger.unit()])?;
+    match trigger {
+        ServiceTrigger::Path => println!("✅ {} enabled, the patch is re-applied whenever Chrome reverts it", trigger.unit()),
+        ServiceTrigger::Timer(interval) => println!("✅ {} enabled, Local State is checked every {interval:?}", trigger.unit()),
+    }
+    println!("   Logs: journalctl --user -u {SERVICE_UNIT}");
+    Ok(())
+}
+
+/// Disable the triggers and remove every unit the tool wrote
+fn uninstall_service(unit_dir: &Path, systemctl: bool, manifest_path: &Path) -> Result<()> {
+    if systemctl {
+        disable_units(&[PATH_UNIT, TIMER_UNIT, SERVICE_UNIT]);
+    }
+    let mut manifest = Manifest::load(manifest_path)?;
+    for name in UNIT_FILES {
+        let path = unit_dir.join(name);
+        remove_own_file(&path, "unit")?;
+        manifest.forget(&path);
+    }
+    manifest.save(manifest_path)?;
+    if systemctl {
+        systemctl_user(&["daemon-reload"])?;
+    }
+    Ok(())
+}
+
+/// Remove a file the tool generated, keeping and reporting files it did not write
+fn remove_own_file(path: &Path, what: &str) -> Result<Removal> {
+    let removal = remove_generated(path).with_context(|| format!("Remove failed: {}", path.display()))?;
+    match removal {
+        Removal::Removed => println!("✅ Removed {}", path.display()),
+        Removal::Kept => {
+            println!("⚠️ Kept {}, it was not created by gemini-unlock", path.display());
+            warn!("Not removing foreign {what} {}", path.display());
+        }
+        Removal::Missing => {}
+    }
+    Ok(removal)
+}
+
+/// Stop and disable units, best effort: they may never have been enabled
+fn disable_units(units: &[&str]) {
+    let mut args = vec!["disable", "--now"];
+    args.extend_from_slice(units);
+    if let Err(err) = systemctl_user(&args) {
+        warn!("Disabling {} failed: {err}", units.join(", "));
+    }
+}
+
+/// Write the package-manager hook and add `users` to the opt-in list
+fn install_hook(root: &Path, manager: PackageManager, users: &[String], manifest_path: &Path) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    let (path, opted_in) = hooks::install_hook(root, manager, users, &exe, manifest_path)?;
+    println!("✅ {manager} hook written: {}", path.display());
+    match manager {
+        PackageManager::Dnf => {
+            println!("   It needs the post-transaction-actions plugin (python3-dnf-plugin-post-transaction-actions)");
+        }
+        PackageManager::Dnf5 => println!("   It needs the actions plugin (libdnf5-plugin-actions)"),
+        _ => {}
+    }
+    if opted_in.is_empty() {
+        println!("⚠️ No user has opted in yet, add one with `hook install --user NAME`");
+        warn!("Hook installed without opted-in users");
     } else {
-        apply_patches_workflow(&chrome_state, &backup_path)?;
+        println!("   Re-patched after Chrome updates: {}", opted_in.join(", "));
+    }
+    Ok(())
+}
+
+/// Opt `users` out, or remove every hook and the opt-in list
+fn remove_hook(root: &Path, users: &[String], manifest_path: &Path) -> Result<()> {
+    if !users.is_empty() {
+        let opted_in: Vec<String> = read_hook_users(root)?
+            .into_iter()
+            .filter(|user| !users.contains(user))
+            .collect();
+        write_hook_users(root, &opted_in, manifest_path)?;
+        println!("✅ Opted out: {}", users.join(", "));
+        return Ok(());
+    }
+
+    let mut manifest = Manifest::load(manifest_path)?;
+    let hooks = PackageManager::ALL.map(|manager| manager.hook_path(root));
+    for path in hooks.iter().chain([&root.join(HOOK_USERS_FILE)]) {
+        remove_own_file(path, "hook file")?;
+        manifest.forget(path);
+    }
+    manifest.save(manifest_path)?;
+    Ok(())
+}
+
+/// Re-apply the patch as each opted-in user; one user failing does not stop the others
+fn run_hook(root: &Path) -> Result<()> {
+    let failed = hooks::run_hook(root, &std::env::current_exe()?)?;
+    for (user, err) in &failed {
+        println!("⚠️ gemini-unlock failed for {user}: {err}");
+    }
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!("gemini-unlock failed for {} user(s)", failed.len()));
+    }
+    Ok(())
+}
+
+/// Pick the rule set for a detected Chrome version, refusing versions without Gemini
+fn select_rule_set(version: Option<ChromeVersion>) -> Result<&'static RuleSet> {
+    let shown = version.map(|version| version.to_string()).unwrap_or_default();
+    let rule_set = match compatibility(version.as_ref()) {
+        Compatibility::Tested(set) => {
+            info!("Chrome {shown}, rule set {}", set.id);
+            set
+        }
+        Compatibility::Untested(set) => {
+            println!("⚠️ Chrome {shown} is newer than the tested releases (up to {}), using rule set {}", set.last_major, set.id);
+            warn!("Untested Chrome {shown}, using rule set {}", set.id);
+            set
+        }
+        Compatibility::Unknown(set) => {
+            println!("⚠️ Chrome version unknown, using rule set {}", set.id);
+            warn!("Chrome version unknown, using rule set {}", set.id);
+            if set.consistency_value(None).is_none() {
+                println!("   {} is left alone, start Chrome once so it records its version", &CONSISTENCY_POINTER[1..]);
+            }
+            set
+        }
+        Compatibility::Unsupported => {
+            return Err(AppError::UnsupportedChromeVersion {
+                version: shown,
+                min: MIN_GEMINI_MAJOR,
+            }
+            .into());
+        }
+    };
+    Ok(rule_set)
+}
+
+/// Show which Local State fields are patched and what the flags files hold
+fn show_status(os: OsKind, chrome_state: &Path, user_data_dir: &Path) -> Result<()> {
+    let chrome_version = detect_chrome_version(os, user_data_dir);
+    let compat = compatibility(chrome_version.as_ref());
+    let version = chrome_version.map_or_else(|| "unknown".to_string(), |version| version.to_string());
+    let rule_set = match compat {
+        Compatibility::Tested(set) => {
+            println!("Chrome {version}, rule set {}", set.id);
+            set
+        }
+        Compatibility::Untested(set) | Compatibility::Unknown(set) => {
+            println!("Chrome {version}, untested; rule set {} would be used", set.id);
+            set
+        }
+        Compatibility::Unsupported => {
+            println!("Chrome {version}, unsupported (Gemini needs Chrome {MIN_GEMINI_MAJOR} or newer)");
+            &RULE_SETS[0]
+        }
+    };
+    match PatchState::load(&user_data_dir.join(STATE_FILE))? {
+        Some(state) => {
+            println!(
+                "Last patch {}: Chrome {}, rule set {}",
+                describe_age(state.applied_at, unix_now()),
+                state.chrome_version.as_deref().unwrap_or("unknown"),
+                state.rule_set
+            );
+            report_state_changes(user_data_dir, chrome_version.as_ref());
+        }
+        None => println!("Not patched yet"),
+    }
+    println!();
+
+    println!("{}:", chrome_state.display());
+    match read_json_if_exists(chrome_state)? {
+        Some(local_state) => {
+            for rule in rule_set.local_state_rules(chrome_version.as_ref()) {
+                match local_state.pointer(&rule.pointer) {
+                    Some(value) if *value == rule.value => {
+                        println!("  ✓ {} = {value}", rule.pointer);
+                    }
+                    Some(value) => println!("  ✗ {} = {value} (wants {})", rule.pointer, rule.value),
+                    None => println!("  • {} not written by Chrome yet", rule.pointer),
+                }
+            }
+        }
+        None => println!("  not found"),
+    }
+
+    println!();
+    println!("Flags files:");
+    let config_home = config_home()?;
+    for channel in [
+        ChromeChannel::Stable,
+        ChromeChannel::Beta,
+        ChromeChannel::Dev,
+        ChromeChannel::Canary,
+        ChromeChannel::Chromium,
+    ] {
+        let path = flags_conf_path(channel, &config_home);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                println!("  ⚠️ {}: {err}", path.display());
+                continue;
+            }
+        };
+        match block_flags(&content) {
+            Ok(Some(flags)) => println!("  ✓ {}: {}", path.display(), flags.join(" ")),
+            Ok(None) => println!("  • {}: no gemini-unlock block", path.display()),
+            Err(err) => println!("  ⚠️ {}: {err}", path.display()),
+        }
+    }
+    Ok(())
+}
+
+/// List the policies `policy install` can write
+fn show_policy_schema() {
+    let width = GEMINI_POLICY_SCHEMA
+        .iter()
+        .map(|schema| schema.name.len())
+        .max()
+        .unwrap_or(0);
+    for schema in GEMINI_POLICY_SCHEMA {
+        let values = match schema.kind {
+            PolicyType::Boolean => "true|false".to_string(),
+            PolicyType::Integer(allowed) => allowed
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>()
+                .join("|"),
+        };
+        println!("{:width$}  {values:10}  {}", schema.name, schema.summary);
     }
+}
 
+/// List installed files and whether they still match what was written
+fn audit_installed(manifest_paths: &[&Path]) -> Result<()> {
+    let mut installed = false;
+    for manifest_path in manifest_paths {
+        let manifest = Manifest::load(manifest_path)?;
+        installed |= !manifest.artifacts.is_empty();
+        for artifact in &manifest.artifacts {
+            let status = match artifact.status()? {
+                ArtifactStatus::Intact => "✓ intact  ",
+                ArtifactStatus::Modified => "⚠️ modified",
+                ArtifactStatus::Missing => "✗ missing ",
+            };
+            println!("{status}  {:19}  {}", artifact.kind, artifact.path.display());
+        }
+    }
+    if !installed {
+        let paths: Vec<String> = manifest_paths.iter().map(|path| path.display().to_string()).collect();
+        println!("Nothing installed (manifests: {})", paths.join(", "));
+    }
     Ok(())
 }
 
-/// Create backup file path
-fn create_backup_path(chrome_state: &PathBuf) -> Result<PathBuf> {
-    chrome_state
-        .file_name()
-        .and_then(|name| name.to_str())
-        .map(|name| chrome_state.with_file_name(format!("{name}.bak")))
-        .ok_or_else(|| AppError::InvalidPath(chrome_state.clone()).into())
+/// Remove every file recorded in the user and the system manifest
+///
+/// Files changed since they were installed are kept unless `force` is set.
+fn uninstall(manifest_path: &Path, system_manifest: &Path, force: bool) -> Result<()> {
+    let mut installed = false;
+    let mut remaining = 0;
+    let mut kept = 0;
+    for path in [manifest_path, system_manifest] {
+        let mut manifest = Manifest::load(path)?;
+        if manifest.artifacts.is_empty() {
+            continue;
+        }
+        installed = true;
+        let (failed, modified) = uninstall_manifest(&mut manifest, force);
+        if failed > 0 && path == system_manifest {
+            println!("   System-wide files need root, run `sudo gemini-unlock uninstall`");
+        }
+        remaining += failed;
+        kept += modified;
+        manifest
+            .save(path)
+            .with_context(|| format!("Write failed: {}", path.display()))?;
+    }
+    if !installed {
+        println!("Nothing to uninstall");
+    }
+    if kept > 0 {
+        println!("   Kept {kept} changed file(s), run `gemini-unlock uninstall --force` to remove them too");
+    }
+    if remaining > 0 {
+        return Err(anyhow::anyhow!(
+            "{remaining} installed file(s) could not be removed"
+        ));
+    }
+    Ok(())
+}
+
+/// Remove the files of one manifest, keeping the records of those that were kept or could not be removed
+///
+/// Returns how many could not be removed and how many changed files were kept.
+fn uninstall_manifest(manifest: &mut Manifest, force: bool) -> (usize, usize) {
+    let units: Vec<&str> = manifest
+        .artifacts
+        .iter()
+        .filter(|artifact| artifact.kind == ArtifactKind::SystemdUnit)
+        .filter(|artifact| force || !matches!(artifact.status(), Ok(ArtifactStatus::Modified)))
+        .filter_map(|artifact| artifact.path.file_name()?.to_str())
+        .collect();
+    let had_units = !units.is_empty();
+    if had_units {
+        disable_units(&units);
+    }
+
+    let (mut failed, mut kept) = (0, 0);
+    for (artifact, outcome) in manifest.uninstall(force) {
+        let path = artifact.path.display().to_string();
+        match outcome {
+            UninstallOutcome::Missing => println!("• {}: {path} already gone", artifact.kind),
+            UninstallOutcome::Removed => println!("✓ {}: {path} removed", artifact.kind),
+            UninstallOutcome::RemovedModified => {
+                println!("✓ {}: {path} removed (it had been modified)", artifact.kind);
+                warn!("Removed modified {} {path}", artifact.kind);
+            }
+            UninstallOutcome::KeptModified => {
+                println!("⚠️ {}: {path} kept, it was changed since it was installed", artifact.kind);
+                warn!("Kept modified {} {path}", artifact.kind);
+                kept += 1;
+            }
+            UninstallOutcome::Failed(reason) => {
+                println!("✗ {}: {path} {reason}", artifact.kind);
+                failed += 1;
+            }
+        }
+    }
+
+    if had_units && let Err(err) = systemctl_user(&["daemon-reload"]) {
+        warn!("systemd reload failed: {err}");
+    }
+
+    (failed, kept)
+}
+
+/// Start the browser that --kill-chrome stopped
+fn relaunch(command: Option<LaunchCommand>) {
+    let Some(command) = command else {
+        warn!("No Chrome command line was recorded, not relaunching");
+        return;
+    };
+    info!(
+        "Relaunching Chrome: {} {}",
+        command.program.display(),
+        command.args.join(" ")
+    );
+    match relaunch_chrome(&command) {
+        Ok(pid) => {
+            println!("🚀 Chrome relaunched (PID {pid})");
+            info!("Chrome relaunched with PID {pid}");
+        }
+        Err(err) => {
+            println!("⚠️ Failed to relaunch Chrome, please start it manually");
+            warn!("Failed to relaunch Chrome: {err}");
+        }
+    }
 }
 
-/// Ensure Chrome is not running
-fn ensure_chrome_not_running(os: platform::OsKind, kill_chrome: bool) -> Result<()> {
-    if is_chrome_running(os)? {
-        if kill_chrome {
+/// Inspect the profile lock, removing a stale one if requested
+fn check_singleton_lock(os: OsKind, user_data_dir: &Path, remove_stale: bool) -> Result<()> {
+    match singleton_lock_status(os, user_data_dir)? {
+        LockStatus::OtherHost(lock) => {
+            error!(
+                "Profile is locked by Chrome (PID {}) on host {}",
+                lock.pid, lock.hostname
+            );
+            return Err(AppError::ProfileLockedElsewhere(lock.hostname).into());
+        }
+        LockStatus::Stale(lock) if remove_stale => {
+            remove_stale_singleton_lock(os, user_data_dir)?;
+            info!("Removed stale SingletonLock of PID {}", lock.pid);
+        }
+        LockStatus::Stale(lock) => {
+            warn!(
+                "Stale SingletonLock of PID {} found, use --remove-stale-lock to clean it up",
+                lock.pid
+            );
+        }
+        LockStatus::Unreadable(target) => warn!("Unrecognized SingletonLock target: {target}"),
+        LockStatus::Held(lock) => info!("Profile is locked by Chrome PID {}", lock.pid),
+        LockStatus::Absent => {}
+    }
+    Ok(())
+}
+
+/// Ensure the Chrome instance using `user_data_dir` is not running
+///
+/// Returns the shutdown report if Chrome had to be stopped.
+fn ensure_chrome_not_running(
+    os: OsKind,
+    user_data_dir: &Path,
+    action: BusyAction,
+) -> Result<Option<ShutdownReport>> {
+    for process in other_chrome_processes(os, user_data_dir)? {
+        if process.is_browser() {
+            info!(
+                "Ignoring Chrome process {} of another user or profile (uid {})",
+                process.pid, process.uid
+            );
+        }
+    }
+
+    if !profile_in_use(os, user_data_dir)? {
+        info!("Chrome is not running");
+        return Ok(None);
+    }
+
+    match action {
+        BusyAction::Kill(shutdown) => {
             info!("Chrome is running, attempting to close...");
-            stop_chrome(os)?;
-            if is_chrome_running(os)? {
+            let report = stop_chrome(os, user_data_dir, shutdown)?;
+            print_shutdown_report(&report);
+            if profile_in_use(os, user_data_dir)? {
                 error!("Chrome is still running, cannot continue");
                 return Err(AppError::ChromeStillRunning.into());
             }
             info!("Chrome closed successfully");
-        } else {
-            error!("Chrome is running, please close it first or use --kill-chrome flag");
-            return Err(AppError::ChromeRunning.into());
+            Ok(Some(report))
+        }
+        BusyAction::Wait(timeout) => {
+            wait_for_chrome_exit(os, user_data_dir, timeout)?;
+            Ok(None)
         }
+        BusyAction::Fail => {
+            error!("Chrome is running, please close it first or use --kill-chrome or --wait");
+            Err(AppError::ChromeRunning.into())
+        }
+    }
+}
+
+/// Wait for the user to close Chrome, showing progress
+fn wait_for_chrome_exit(os: OsKind, user_data_dir: &Path, timeout: Option<Duration>) -> Result<()> {
+    match timeout {
+        Some(timeout) => info!("Waiting up to {timeout:?} for Chrome to be closed..."),
+        None => info!("Waiting for Chrome to be closed..."),
+    }
+
+    let interactive = std::io::stderr().is_terminal();
+    let spinner = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    let mut ticks = 0usize;
+    let mut last_logged = 0;
+    let closed = wait_for_profile_release(
+        os,
+        user_data_dir,
+        timeout,
+        Duration::from_millis(250),
+        |elapsed| {
+            let secs = elapsed.as_secs();
+            if interactive {
+                eprint!(
+                    "\r{} Waiting for Chrome to exit... {secs}s ",
+                    spinner[ticks % spinner.len()]
+                );
+                let _ = std::io::stderr().flush();
+                ticks += 1;
+            } else if secs >= last_logged + 30 {
+                info!("Still waiting for Chrome to exit ({secs}s)");
+                last_logged = secs;
+            }
+        },
+    )?;
+    if interactive && ticks > 0 {
+        eprintln!();
+    }
+
+    match (closed, timeout) {
+        (true, _) => {
+            info!("Chrome was closed, continuing immediately");
+            Ok(())
+        }
+        (false, Some(timeout)) => {
+            error!("Chrome was not closed within {timeout:?}");
+            Err(AppError::WaitTimedOut(timeout).into())
+        }
+        (false, None) => unreachable!("waiting without a timeout only returns once Chrome exits"),
+    }
+}
+
+/// Print every field below a key matching the patterns, with its type and value
+fn discover_local_state_fields(path: &Path, patterns: &[String], suggest: bool) -> Result<()> {
+    let json: Value = serde_json::from_str(&read_local_state(path)?)
+        .map_err(|e| AppError::InvalidJson(format!("{} parsing failed: {e}", path.display())))?;
+    let found = if patterns.is_empty() {
+        discover_fields(&json, DISCOVERY_PATTERNS)
     } else {
-        info!("Chrome is not running");
+        discover_fields(&json, patterns)
+    };
+    if found.is_empty() {
+        println!("No matching keys found in {}", path.display());
+        return Ok(());
+    }
+
+    println!("{}:", path.display());
+    let pointer_width = found.iter().map(|d| d.pointer.chars().count()).max().unwrap_or(0);
+    let mut suggested = 0;
+    for discovery in &found {
+        let value = match &discovery.value {
+            Value::Object(map) => format!("{{{} keys}}", map.len()),
+            Value::Array(items) if items.iter().any(|item| item.is_object() || item.is_array()) => {
+                format!("[{} items]", items.len())
+            }
+            value => value.to_string(),
+        };
+        println!(
+            "  {:pointer_width$}  {:7}  {value}  ({})",
+            discovery.pointer,
+            discovery.kind(),
+            discovery.pattern
+        );
+        if suggest && let Some(rule) = suggest_rule(discovery) {
+            println!("    → rule: {} = {} (only if present)", rule.pointer, rule.value);
+            suggested += 1;
+        }
+    }
+    println!();
+    println!("{} field(s) found", found.len());
+    if suggest {
+        println!("{suggested} rule suggestion(s)");
+    }
+    Ok(())
+}
+
+/// List profiles from Local State
+fn show_profiles(chrome_state: &Path) -> Result<()> {
+    let profiles = list_profiles(&read_local_state(chrome_state)?)?;
+    if profiles.is_empty() {
+        println!("No profiles found in {}", chrome_state.display());
+        return Ok(());
+    }
+
+    let dir_width = profiles.iter().map(|p| p.dir.len()).max().unwrap_or(0);
+    let name_width = profiles.iter().map(|p| p.name.chars().count()).max().unwrap_or(0);
+    for profile in &profiles {
+        let mut notes = Vec::new();
+        if profile.last_used {
+            notes.push("last used".to_string());
+        }
+        if profile.managed {
+            match &profile.hosted_domain {
+                Some(domain) => notes.push(format!("managed by {domain}")),
+                None => notes.push("managed".to_string()),
+            }
+        }
+        let notes = if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", notes.join(", "))
+        };
+        println!(
+            "{} {:dir_width$}  {:name_width$}  {}{}",
+            if profile.last_used { "*" } else { " " },
+            profile.dir,
+            profile.name,
+            profile.user_name.as_deref().unwrap_or("-"),
+            notes
+        );
+    }
+    Ok(())
+}
+
+/// Run the doctor checks, applying automatic fixes if requested
+fn run_doctor(target: &DoctorTarget, fix: bool) -> Result<()> {
+    report_state_changes(target.user_data_dir, target.chrome_version.as_ref());
+    let input = doctor_input(target);
+    let mut results = run_checks(&input);
+    print_checks(&results);
+
+    let fixes: Vec<Fix> = results
+        .iter()
+        .filter(|result| result.status != CheckStatus::Pass)
+        .filter_map(|result| result.fix)
+        .collect();
+    if !fixes.is_empty() && !fix {
+        println!();
+        println!("💡 Run `gemini-unlock doctor --fix` to repair the checks marked [fixable]");
+    } else if !fixes.is_empty() && input.chrome_running {
+        println!();
+        println!("⚠️ Chrome is running, close it before using --fix");
+    } else if !fixes.is_empty() {
+        println!();
+        info!("Applying doctor fixes: {fixes:?}");
+        apply_fixes(target, &fixes)?;
+
+        println!();
+        println!("After fixes:");
+        results = run_checks(&doctor_input(target));
+        print_checks(&results);
+    }
+
+    let failed = results
+        .iter()
+        .filter(|result| result.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        return Err(AppError::DoctorFailed(failed).into());
+    }
+    Ok(())
+}
+
+/// Run the repairs of the failed checks; only a patch fix writes the profiles
+fn apply_fixes(target: &DoctorTarget, fixes: &[Fix]) -> Result<()> {
+    if fixes.contains(&Fix::ApplyPatches) {
+        let options = ApplyOptions {
+            chrome_version: target.chrome_version,
+            rule_set: select_rule_set(target.chrome_version)?,
+            profile: target.profile,
+            locale: fixes.contains(&Fix::SetLocale).then_some(DEFAULT_REGION.locale),
+            accept_languages: false,
+            sync_safe_seed: fixes.contains(&Fix::SyncSafeSeed),
+            dry_run: false,
+            pref_hash: target.pref_hash,
+            backed_up: &[],
+        };
+        return apply_and_report(target.chrome_state, target.user_data_dir, &options);
+    }
+
+    let mut rules = Vec::new();
+    if fixes.contains(&Fix::SetLocale) {
+        rules.extend(locale_rules(DEFAULT_REGION.locale));
+    }
+    if fixes.contains(&Fix::SyncSafeSeed) {
+        rules.extend(safe_seed_rules());
+    }
+    let (report, transaction) = patch_local_state(target.chrome_state, target.user_data_dir, &rules)?;
+    print_rule_report(LOCAL_STATE_LABEL, &report);
+    match transaction {
+        Some(transaction) => finish_transaction(transaction),
+        None => Ok(()),
+    }
+}
+
+/// Where the doctor looks
+struct DoctorTarget<'a> {
+    os: OsKind,
+    chrome_version: Option<ChromeVersion>,
+    chrome_state: &'a Path,
+    user_data_dir: &'a Path,
+    policy_root: &'a Path,
+    profile: Option<&'a str>,
+    pref_hash: &'a PrefHashConfig,
+}
+
+/// Gather everything the doctor checks look at
+///
+/// Inputs that cannot be read are listed in `unreadable` for the checks to
+/// report instead of failing the whole run.
+fn doctor_input(target: &DoctorTarget) -> DoctorInput {
+    let DoctorTarget {
+        os,
+        chrome_state,
+        user_data_dir,
+        policy_root,
+        profile,
+        ..
+    } = *target;
+    let mut unreadable = Vec::new();
+    let mut note = |label: &str, err: &dyn std::fmt::Display| {
+        warn!("doctor could not read {label}: {err}");
+        unreadable.push((label.to_string(), err.to_string()));
+    };
+
+    let (local_state_content, local_state) = read_local_state(chrome_state)
+        .and_then(|content| {
+            serde_json::from_str::<Value>(&content)
+                .map(|json| (content, json))
+                .map_err(|e| AppError::InvalidJson(format!("Local State parsing failed: {e}")))
+        })
+        .unwrap_or_else(|err| {
+            note(LOCAL_STATE_LABEL, &err);
+            ("{}".to_string(), Value::Null)
+        });
+    let managed: Vec<String> = match list_profiles(&local_state_content) {
+        Ok(profiles) => profiles
+            .into_iter()
+            .filter(|profile| profile.managed)
+            .map(|profile| profile.dir)
+            .collect(),
+        Err(err) => {
+            note("profile list", &err);
+            Vec::new()
+        }
+    };
+    let dirs = target_profile_dirs(&local_state_content, profile).unwrap_or_else(|err| {
+        note("profile selection", &err);
+        Vec::new()
+    });
+    let policies = read_policies(os, policy_root).unwrap_or_else(|err| {
+        note("policies", &err);
+        Vec::new()
+    });
+    let chrome_running = profile_in_use(os, user_data_dir).unwrap_or_else(|err| {
+        note("profile lock", &err);
+        false
+    });
+
+    let mut input = DoctorInput {
+        policies: gemini_findings(&policies),
+        cloud_hints: cloud_policy_hints(&local_state),
+        local_state,
+        chrome_running,
+        chrome_version: target.chrome_version.map(|version| version.to_string()),
+        system_locale: ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty()),
+        pref_hash_error: target.pref_hash.hasher().err().map(|err| err.to_string()),
+        proxy_env: PROXY_ENV_VARS
+            .iter()
+            .filter_map(|name| std::env::var(name).ok().map(|value| (name.to_string(), value)))
+            .filter(|(_, value)| !value.is_empty())
+            .collect(),
+        unreadable,
+        ..Default::default()
+    };
+
+    let mut files = Vec::new();
+    if chrome_state.exists() {
+        files.push((LOCAL_STATE_LABEL.to_string(), chrome_state.to_path_buf()));
+    }
+    for dir in dirs {
+        let Some(preferences) = preferences_path(user_data_dir, &dir) else {
+            continue;
+        };
+        input.profiles.push(ProfileState {
+            dir: dir.clone(),
+            preferences: fs::read_to_string(&preferences)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok()),
+            managed: managed.contains(&dir),
+        });
+        if preferences.exists() {
+            files.push((format!("{dir}/{PREFERENCES_FILE}"), preferences));
+        }
+    }
+
+    input.writable.push(("user-data dir".to_string(), is_writable(user_data_dir)));
+    for (label, path) in files {
+        if let Ok(backup) = backup_path(&path) {
+            let content = fs::read_to_string(&backup).ok();
+            input.backups.push((format!("{label}.bak"), BackupHealth::of(content.as_deref())));
+        }
+        input.writable.push((label, is_writable(&path)));
+    }
+    input
+}
+
+/// Print doctor check results
+fn print_checks(results: &[CheckResult]) {
+    let width = results.iter().map(|result| result.name.len()).max().unwrap_or(0);
+    for result in results {
+        let symbol = match result.status {
+            CheckStatus::Pass => "✓",
+            CheckStatus::Warn => "⚠️",
+            CheckStatus::Fail => "✗",
+        };
+        let fixable = if result.fix.is_some() { " [fixable]" } else { "" };
+        println!("{symbol} {:width$}  {}{fixable}", result.name, result.message);
+        if let Some(hint) = &result.hint {
+            println!("  {:width$}  → {hint}", "");
+        }
+        info!("doctor {}: {} ({})", result.name, result.status, result.message);
+    }
+}
+
+/// Check the preference MACs of each target profile
+fn verify_profile_macs(
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    profile: Option<&str>,
+    hasher: &PrefHasher,
+) -> Result<()> {
+    let local_state = read_local_state(chrome_state)?;
+    let mut rejected = 0;
+    for dir in target_profile_dirs(&local_state, profile)? {
+        let Some(preferences) = preferences_path(user_data_dir, &dir) else {
+            continue;
+        };
+        let secure = preferences.with_file_name(SECURE_PREFERENCES_FILE);
+        let files = [
+            (SECURE_PREFERENCES_FILE, read_json_if_exists(&secure)?),
+            (PREFERENCES_FILE, read_json_if_exists(&preferences)?),
+        ];
+
+        for (index, (name, store)) in files.iter().enumerate() {
+            let Some(store) = store else {
+                continue;
+            };
+            let others: Vec<&Value> = files[1 - index].1.iter().collect();
+            let report = verify_macs(store, &others, hasher);
+            if report.prefs.is_empty() && report.super_mac.is_none() {
+                continue;
+            }
+            for check in report.rejected() {
+                println!("✗ Profile {dir}: {} ({name}) would be reset by Chrome", check.path);
+                warn!("Profile {dir}: MAC of {} in {name} is invalid", check.path);
+            }
+            if report.super_mac == Some(MacStatus::Invalid) {
+                println!("✗ Profile {dir}: super_mac of {name} is invalid, Chrome would distrust its MACs");
+                warn!("Profile {dir}: super_mac of {name} is invalid");
+            }
+            if report.all_accepted() {
+                println!("✓ Profile {dir}: all {} MACs in {name} are valid", report.prefs.len());
+            }
+            rejected += report.rejected().count();
+        }
+    }
+    if rejected > 0 {
+        println!();
+        println!("⚠️ {rejected} protected preference(s) would be reset on next launch");
     }
     Ok(())
 }
 
+/// Mark the profiles of a force-stopped Chrome as cleanly exited
+///
+/// Returns the Preferences files backed up before the edit, if `backup` is set.
+fn mark_profiles_clean_exit(
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    profile: Option<&str>,
+    backup: bool,
+) -> Result<Vec<PathBuf>> {
+    let local_state = read_local_state(chrome_state)?;
+    let dirs = match profile {
+        Some(selector) => vec![selected_profile(&local_state, selector)?.dir],
+        None => open_profile_dirs(&local_state)?,
+    };
+    let mut backed_up = Vec::new();
+    for dir in dirs {
+        let Some(preferences) = preferences_path(user_data_dir, &dir) else {
+            continue;
+        };
+        if !preferences.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&preferences)
+            .with_context(|| format!("Read failed: {}", preferences.display()))?;
+        let Some(updated) = mark_clean_exit(&content)? else {
+            info!("Profile {dir} already marked as cleanly exited");
+            continue;
+        };
+
+        if backup {
+            backup_file(&preferences)
+                .with_context(|| format!("Backup failed: {}", preferences.display()))?;
+            backed_up.push(preferences.clone());
+        }
+        write_atomic(&preferences, &updated)
+            .with_context(|| format!("Write failed: {}", preferences.display()))?;
+        println!("✓ Marked profile {dir} as cleanly exited");
+        info!("Set profile.exit_type = Normal in {}", preferences.display());
+    }
+    Ok(backed_up)
+}
+
 /// Restore configuration from backup
-fn restore_from_backup(backup_path: &PathBuf, chrome_state: &PathBuf) -> Result<()> {
+fn restore_from_backup(backup_path: &Path, chrome_state: &Path) -> Result<()> {
     if !backup_path.exists() {
         error!("Backup file not found: {}", backup_path.display());
-        return Err(AppError::BackupNotFound(backup_path.clone()).into());
+        return Err(AppError::BackupNotFound(backup_path.to_path_buf()).into());
     }
     info!("Restoring from backup...");
     fs::copy(backup_path, chrome_state)
@@ -97,66 +1638,242 @@ fn restore_from_backup(backup_path: &PathBuf, chrome_state: &PathBuf) -> Result<
     Ok(())
 }
 
-/// Apply patches workflow
-fn apply_patches_workflow(chrome_state: &PathBuf, backup_path: &PathBuf) -> Result<()> {
-    // Check if config file exists
-    if !chrome_state.exists() {
-        error!("Chrome config file not found: {}", chrome_state.display());
-        return Err(AppError::ConfigNotFound(chrome_state.clone()).into());
+/// Restore profile Preferences from their backups
+fn restore_profiles(chrome_state: &Path, user_data_dir: &Path, profile: Option<&str>) -> Result<()> {
+    let local_state = read_local_state(chrome_state)?;
+    for dir in target_profile_dirs(&local_state, profile)? {
+        let Some(preferences) = preferences_path(user_data_dir, &dir) else {
+            continue;
+        };
+        let backup = backup_path(&preferences)?;
+        if !backup.exists() {
+            continue;
+        }
+        fs::copy(&backup, &preferences)
+            .with_context(|| format!("Restore failed: {}", preferences.display()))?;
+        println!("✅ Restored Preferences of profile {dir}");
+        info!("Restored {}", preferences.display());
     }
+    Ok(())
+}
 
-    // Create backup
-    info!("Creating backup to: {}", backup_path.display());
-    fs::copy(chrome_state, backup_path)
-        .with_context(|| format!("Backup failed: {}", backup_path.display()))?;
-    info!("Backup completed");
+/// Remove the patch state after a restore, so no update is reported for an unpatched Chrome
+fn forget_patch_state(user_data_dir: &Path) -> Result<()> {
+    let path = user_data_dir.join(STATE_FILE);
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("Remove failed: {}", path.display())),
+    }
+}
 
-    // Read, modify and write configuration
-    info!("Reading config file...");
-    let content = fs::read_to_string(chrome_state)
-        .with_context(|| format!("Read failed: {}", chrome_state.display()))?;
-    info!("Config file size: {} bytes", content.len());
+/// Warn when Chrome or the rule set changed since the last patch
+fn report_state_changes(user_data_dir: &Path, version: Option<&ChromeVersion>) {
+    let state = match PatchState::load(&user_data_dir.join(STATE_FILE)) {
+        Ok(Some(state)) => state,
+        Ok(None) => return,
+        Err(err) => {
+            warn!("Patch state unreadable: {err}");
+            return;
+        }
+    };
+    let Some(rule_set) = compatibility(version).rule_set() else {
+        return;
+    };
+    let changes = state.changes(version, rule_set.id);
+    for change in &changes {
+        println!("⚠️ Since the last patch, {change}");
+        warn!("Since the last patch, {change}");
+    }
+    if !changes.is_empty() {
+        println!("   Run `gemini-unlock reapply` to patch again with the same options");
+    }
+}
 
-    info!("Applying patches...");
-    let report = apply_patches(&content)?;
+/// Patch Local State and every target profile's Preferences, printing what changed
+fn apply_and_report(chrome_state: &Path, user_data_dir: &Path, options: &ApplyOptions) -> Result<()> {
+    let report = apply_workflow(chrome_state, user_data_dir, options)?;
+    print_patch_report(&report.patch);
+    if let Some(rules) = &report.local_state {
+        print_rule_report(LOCAL_STATE_LABEL, rules);
+    }
+    for profile in &report.profiles {
+        let Some(rules) = &profile.rules else {
+            println!("⚠️ Profile {}: no Preferences file, skipped", profile.dir);
+            continue;
+        };
+        print_rule_report(&format!("Profile {}", profile.dir), rules);
+        if profile.sealed > 0 {
+            println!("✓ Profile {}: re-sealed {} protected preference MAC(s)", profile.dir, profile.sealed);
+            info!("Profile {}: recomputed {} MAC(s) and the super_mac", profile.dir, profile.sealed);
+        }
+    }
+    match report.transaction {
+        Some(transaction) => finish_transaction(transaction),
+        None => {
+            info!("Dry run, not writing any file");
+            Ok(())
+        }
+    }
+}
 
-    // Display results before writing
-    print_patch_report(&report);
+/// Put journaled original values back, printing what was restored
+fn revert_and_report(
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    profile: Option<&str>,
+    pref_hash: &PrefHashConfig,
+) -> Result<()> {
+    let report = revert_journal(chrome_state, user_data_dir, profile, pref_hash)?;
+    if let Some(transaction) = report.transaction {
+        finish_transaction(transaction)?;
+    }
+    for (label, count) in report.restored {
+        println!("✅ Restored {count} original value(s) in {label}");
+        info!("Reverted {count} journaled field(s) in {label}");
+    }
+    Ok(())
+}
 
-    info!("Writing config file...");
-    fs::write(chrome_state, report.content)
-        .with_context(|| format!("Write failed: {}", chrome_state.display()))?;
+/// Print a committed transaction and fail if it was rolled back
+fn finish_transaction(report: TransactionReport) -> Result<()> {
+    print_transaction_report(&report);
+    report.into_result()?;
     info!("Write completed");
-
     Ok(())
 }
 
-/// Print patch application results
-fn print_patch_report(report: &crate::patcher::PatchReport) {
-    println!();
-    if report.changed_is_glic {
-        println!("✓ Enabled is_glic_eligible");
-        info!("Modified is_glic_eligible = true");
-    } else {
-        println!("⚠️ is_glic_eligible field not found");
-        warn!("is_glic_eligible field not found");
+/// Print the outcome of each file in a transaction
+fn print_transaction_report(report: &TransactionReport) {
+    for file in &report.files {
+        match file.outcome {
+            FileOutcome::Committed => info!("{}: {}", file.label, file.outcome),
+            _ => {
+                println!("⚠️ {}: {}", file.label, file.outcome);
+                warn!("{} ({}): {}", file.label, file.path.display(), file.outcome);
+            }
+        }
     }
-    if report.changed_variations_country {
-        println!("✓ Set variations_country = us");
-        info!("Modified variations_country = us");
+    if report.is_success() {
+        println!("💾 Wrote {} file(s)", report.files.len());
     } else {
-        println!("⚠️ variations_country field not found");
-        warn!("variations_country field not found");
+        println!("❌ Write failed, already written files were rolled back");
     }
-    if report.changed_variations_permanent_country {
-        println!("✓ Set variations_permanent_consistency_country = us");
-        info!("Modified variations_permanent_consistency_country = [\"us\"]");
-    } else {
-        println!("⚠️ variations_permanent_consistency_country field not found");
-        warn!("variations_permanent_consistency_country field not found");
+}
+
+/// Print the rule changes of one file
+fn print_rule_report(label: &str, report: &RuleReport) {
+    for change in &report.changes {
+        let old = change
+            .old
+            .as_ref()
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "(missing)".to_string());
+        println!("✓ {label}: set {} = {} (was {old})", change.pointer, change.new);
+        info!("{label}: modified {} = {}", change.pointer, change.new);
+    }
+    for pointer in &report.unchanged {
+        println!("• {label}: {pointer} already set");
+    }
+    for pointer in &report.missing {
+        println!("⚠️ {label}: {pointer} could not be set");
+        warn!("{label}: {pointer} could not be set");
+    }
+}
+
+/// Print the shutdown stages that ran
+fn print_shutdown_report(report: &ShutdownReport) {
+    for stage in &report.stages {
+        let outcome = if stage.stopped { "exited" } else { "still running" };
+        println!(
+            "• Shutdown stage '{}' → PIDs {:?}: {} after {:.1}s",
+            stage.action,
+            stage.pids,
+            outcome,
+            stage.elapsed.as_secs_f32()
+        );
+        info!(
+            "Shutdown stage '{}' targeted {:?}, {} after {:?}",
+            stage.action, stage.pids, outcome, stage.elapsed
+        );
+    }
+}
+
+/// Warn about policies that keep the patch from taking effect
+fn report_policies(os: OsKind, policy_root: &Path, chrome_state: &Path) {
+    let findings = match read_policies(os, policy_root) {
+        Ok(entries) => gemini_findings(&entries),
+        Err(err) => {
+            warn!("Failed to read Chrome policies: {err}");
+            Vec::new()
+        }
+    };
+    let hints = fs::read_to_string(chrome_state)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .map(|local_state| cloud_policy_hints(&local_state))
+        .unwrap_or_default();
+    if findings.is_empty() && hints.is_empty() {
+        return;
+    }
+
+    println!();
+    for finding in &findings {
+        println!(
+            "⚠️ Policy {} ({}, {})",
+            finding.reason,
+            finding.entry.level,
+            finding.entry.source.display()
+        );
+        warn!("Policy {} in {}", finding.reason, finding.entry.source.display());
+        if finding.is_enforced_block() {
+            println!("   Gemini stays disabled while this policy is set, ask your administrator");
+        } else if finding.entry.level == PolicyLevel::Recommended {
+            println!("   This is only a default, you can change it in Chrome settings");
+        }
+    }
+    if !hints.is_empty() {
+        println!(
+            "⚠️ Chrome is enrolled in cloud management ({}), cloud policies may disable Gemini",
+            hints.join(", ")
+        );
+        warn!("Cloud policy hints in Local State: {}", hints.join(", "));
     }
+}
 
+/// Print the final status line
+fn print_done(dry_run: bool) {
     println!();
-    println!("✅ Done, please restart Chrome");
+    if dry_run {
+        println!("🔍 Dry run, no files were changed");
+    } else {
+        println!("✅ Done, please restart Chrome");
+    }
     info!("All operations completed");
 }
+
+/// Print patch application results
+fn print_patch_report(report: &PatchReport) {
+    println!();
+    for field in &report.fields {
+        match &field.outcome {
+            FieldOutcome::Written(value) => {
+                println!("✓ Set {} = {value}", field.key);
+                info!("Modified {} = {value}", field.key);
+            }
+            FieldOutcome::Skipped => {
+                println!("• {} left alone", field.key);
+                info!("Skipped {}", field.key);
+            }
+            FieldOutcome::NotFound => {
+                println!("⚠️ {} field not found", field.key);
+                warn!("{} field not found", field.key);
+            }
+        }
+    }
+    if report.fields.iter().any(|field| field.outcome == FieldOutcome::NotFound) {
+        println!("   Run `gemini-unlock discover-fields` to look for fields Chrome moved or renamed");
+    }
+
+    println!();
+}
@@ -0,0 +1,386 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, AppResult};
+use crate::files::write_atomic;
+use crate::flagsconf::{block_text, remove_block_from};
+use crate::prefhash::encode_hex;
+
+/// Manifest file name, kept next to `config.json`.
+pub const MANIFEST_FILE: &str = "installed.json";
+
+/// Manifest of system-wide files installed as root, relative to the policy root.
+pub const SYSTEM_MANIFEST: &str = "var/lib/gemini-unlock/installed.json";
+
+/// What a file installed outside the Chrome profile is for.
+///
+/// # Examples
+///
+/// ```text
+/// ArtifactKind::PolicyFile
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// Chrome policy JSON written by `policy install`.
+    PolicyFile,
+    /// `.desktop` override written by `launcher`.
+    DesktopEntry,
+    /// Shell wrapper written by `launcher`.
+    LauncherScript,
+    /// `initial_preferences` created by `initial-prefs`.
+    InitialPreferences,
+    /// Marked block in a user's flags file written by `flags-conf`; only the
+    /// block belongs to the tool.
+    FlagsBlock,
+    /// systemd user unit written by `install-service`.
+    SystemdUnit,
+    /// Package-manager hook written by `hook install`.
+    PackageHook,
+    /// Opt-in list of the package-manager hook.
+    HookUsers,
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PolicyFile => "policy file",
+            Self::DesktopEntry => "desktop entry",
+            Self::LauncherScript => "launcher script",
+            Self::InitialPreferences => "initial preferences",
+            Self::FlagsBlock => "flags block",
+            Self::SystemdUnit => "systemd unit",
+            Self::PackageHook => "package hook",
+            Self::HookUsers => "hook user list",
+        })
+    }
+}
+
+/// A file the tool installed, with the hash of what it wrote.
+///
+/// # Examples
+///
+/// ```text
+/// Artifact { kind: ArtifactKind::PolicyFile, path: "/etc/opt/chrome/policies/recommended/gemini-unlock.json".into(), sha256: "…".into() }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// What the file is for.
+    pub kind: ArtifactKind,
+    /// Absolute path of the file.
+    pub path: PathBuf,
+    /// Lowercase hex SHA-256 of the content the tool wrote.
+    pub sha256: String,
+}
+
+/// State of an installed file compared to what the tool wrote.
+///
+/// # Examples
+///
+/// ```text
+/// ArtifactStatus::Modified
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArtifactStatus {
+    /// The file still has the content the tool wrote.
+    Intact,
+    /// The file was changed since it was installed.
+    Modified,
+    /// The file no longer exists.
+    Missing,
+}
+
+impl Artifact {
+    /// Describe a file about to be written with `content` (for a flags
+    /// block, the block text only).
+    pub fn new(kind: ArtifactKind, path: impl Into<PathBuf>, content: &str) -> Self {
+        Self {
+            kind,
+            path: path.into(),
+            sha256: content_hash(content.as_bytes()),
+        }
+    }
+
+    /// Compare the file on disk with what was written.
+    pub fn status(&self) -> AppResult<ArtifactStatus> {
+        let content = match fs::read(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(ArtifactStatus::Missing);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let owned = match self.kind {
+            ArtifactKind::FlagsBlock => {
+                match block_text(&String::from_utf8_lossy(&content))? {
+                    Some(block) => block.into_bytes(),
+                    None => return Ok(ArtifactStatus::Missing),
+                }
+            }
+            _ => content,
+        };
+        if content_hash(&owned) == self.sha256 {
+            Ok(ArtifactStatus::Intact)
+        } else {
+            Ok(ArtifactStatus::Modified)
+        }
+    }
+
+    /// Remove what the tool installed: the whole file, or only the block of a
+    /// flags file. A flags file left empty is removed too.
+    pub fn remove(&self) -> AppResult<()> {
+        if self.kind != ArtifactKind::FlagsBlock {
+            return Ok(fs::remove_file(&self.path)?);
+        }
+        remove_block_from(&self.path).map(|_| ())
+    }
+}
+
+/// Every file the tool installed outside the Chrome profile.
+///
+/// `audit` lists them and `uninstall` removes them again.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::manifest::{Artifact, ArtifactKind, Manifest};
+///
+/// let mut manifest = Manifest::default();
+/// manifest.record(Artifact::new(ArtifactKind::PolicyFile, "/etc/a.json", "{}"));
+/// manifest.record(Artifact::new(ArtifactKind::PolicyFile, "/etc/a.json", "{\"x\":1}"));
+/// assert_eq!(manifest.artifacts.len(), 1);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    /// Installed files, in installation order.
+    pub artifacts: Vec<Artifact>,
+}
+
+impl Manifest {
+    /// Load a manifest, returning an empty one if the file does not exist.
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+        serde_json::from_str(&content).map_err(|e| {
+            AppError::InvalidJson(format!("Manifest {} is invalid: {e}", path.display()))
+        })
+    }
+
+    /// Write the manifest, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::InvalidJson(format!("Manifest serialization failed: {e}")))?;
+        write_atomic(path, &content)
+    }
+
+    /// Add an artifact, replacing an earlier record of the same path.
+    pub fn record(&mut self, artifact: Artifact) {
+        match self.artifacts.iter_mut().find(|a| a.path == artifact.path) {
+            Some(existing) => *existing = artifact,
+            None => self.artifacts.push(artifact),
+        }
+    }
+
+    /// Forget the artifact at `path`, returning it if it was recorded.
+    pub fn forget(&mut self, path: &Path) -> Option<Artifact> {
+        let index = self.artifacts.iter().position(|a| a.path == path)?;
+        Some(self.artifacts.remove(index))
+    }
+
+    /// Remove every recorded file, keeping the records of those that were
+    /// kept or could not be removed.
+    ///
+    /// Files changed since they were installed are kept unless `force` is
+    /// set. Systemd units are only deleted; the caller disables them first.
+    pub fn uninstall(&mut self, force: bool) -> Vec<(Artifact, UninstallOutcome)> {
+        let mut outcomes = Vec::new();
+        for artifact in std::mem::take(&mut self.artifacts) {
+            let outcome = match artifact.status() {
+                Ok(ArtifactStatus::Missing) => UninstallOutcome::Missing,
+                Ok(ArtifactStatus::Modified) if !force => UninstallOutcome::KeptModified,
+                Ok(status) => match artifact.remove() {
+                    Ok(()) if status == ArtifactStatus::Modified => {
+                        UninstallOutcome::RemovedModified
+                    }
+                    Ok(()) => UninstallOutcome::Removed,
+                    Err(err) => UninstallOutcome::Failed(format!("could not be removed: {err}")),
+                },
+                Err(err) => UninstallOutcome::Failed(format!("could not be checked: {err}")),
+            };
+            if matches!(
+                outcome,
+                UninstallOutcome::KeptModified | UninstallOutcome::Failed(_)
+            ) {
+                self.artifacts.push(artifact.clone());
+            }
+            outcomes.push((artifact, outcome));
+        }
+        outcomes
+    }
+}
+
+/// What [`Manifest::uninstall`] did with one artifact.
+///
+/// # Examples
+///
+/// ```text
+/// UninstallOutcome::RemovedModified
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UninstallOutcome {
+    /// The file was already gone.
+    Missing,
+    /// The file was removed.
+    Removed,
+    /// The file was changed since it was installed and removed anyway (`--force`).
+    RemovedModified,
+    /// The file was changed since it was installed and kept; its record is kept.
+    KeptModified,
+    /// The file could not be checked or removed; its record is kept.
+    Failed(String),
+}
+
+/// Manifest location for a config file path.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::manifest::manifest_path;
+///
+/// let path = manifest_path(Path::new("/home/me/.config/gemini-unlock/config.json"));
+/// assert_eq!(path, Path::new("/home/me/.config/gemini-unlock/installed.json"));
+/// ```
+pub fn manifest_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(MANIFEST_FILE)
+}
+
+/// Manifest location for system-wide files (policy files, initial
+/// preferences and package hooks), below `root`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::manifest::system_manifest_path;
+///
+/// let path = system_manifest_path(Path::new("/"));
+/// assert_eq!(path, Path::new("/var/lib/gemini-unlock/installed.json"));
+/// ```
+pub fn system_manifest_path(root: &Path) -> PathBuf {
+    root.join(SYSTEM_MANIFEST)
+}
+
+fn content_hash(content: &[u8]) -> String {
+    encode_hex(&Sha256::digest(content)).to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flagsconf::set_block;
+
+    #[test]
+    fn status_tracks_file_changes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("policy.json");
+        let artifact = Artifact::new(ArtifactKind::PolicyFile, &path, "{}");
+        assert_eq!(artifact.status().unwrap(), ArtifactStatus::Missing);
+
+        fs::write(&path, "{}").unwrap();
+        assert_eq!(artifact.status().unwrap(), ArtifactStatus::Intact);
+
+        fs::write(&path, "{\"GeminiSettings\":1}").unwrap();
+        assert_eq!(artifact.status().unwrap(), ArtifactStatus::Modified);
+    }
+
+    #[test]
+    fn flags_block_is_tracked_and_removed_alone() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("chrome-flags.conf");
+        let content = set_block("--ozone-platform=wayland\n", &["--enable-features=Glic".into()])
+            .unwrap();
+        fs::write(&path, &content).unwrap();
+        let block = block_text(&content).unwrap().unwrap();
+        let artifact = Artifact::new(ArtifactKind::FlagsBlock, &path, &block);
+        assert_eq!(artifact.status().unwrap(), ArtifactStatus::Intact);
+
+        fs::write(&path, content.replace("wayland", "x11")).unwrap();
+        assert_eq!(artifact.status().unwrap(), ArtifactStatus::Intact);
+
+        artifact.remove().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "--ozone-platform=x11\n");
+        assert_eq!(artifact.status().unwrap(), ArtifactStatus::Missing);
+    }
+
+    #[test]
+    fn round_trips_through_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = manifest_path(&dir.path().join("sub").join("config.json"));
+        assert_eq!(Manifest::load(&path).unwrap(), Manifest::default());
+
+        let mut manifest = Manifest::default();
+        manifest.record(Artifact::new(ArtifactKind::PolicyFile, "/etc/a.json", "{}"));
+        manifest.save(&path).unwrap();
+        let mut loaded = Manifest::load(&path).unwrap();
+        assert_eq!(loaded, manifest);
+
+        assert!(loaded.forget(Path::new("/etc/a.json")).is_some());
+        assert!(loaded.forget(Path::new("/etc/a.json")).is_none());
+        assert!(loaded.artifacts.is_empty());
+    }
+
+    #[test]
+    fn uninstall_removes_files_and_keeps_failures() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let intact = dir.path().join("intact.json");
+        let modified = dir.path().join("modified.json");
+        let gone = dir.path().join("gone.json");
+        let blocked = dir.path().join("blocked");
+        fs::write(&intact, "{}").unwrap();
+        fs::write(&modified, "{\"x\":1}").unwrap();
+        fs::create_dir(&blocked).unwrap();
+        fs::write(blocked.join("file"), "").unwrap();
+
+        let mut manifest = Manifest::default();
+        for path in [&intact, &modified, &gone, &blocked] {
+            manifest.record(Artifact::new(ArtifactKind::PolicyFile, path, "{}"));
+        }
+        let outcomes = |manifest: &mut Manifest, force| -> Vec<UninstallOutcome> {
+            manifest
+                .uninstall(force)
+                .into_iter()
+                .map(|(_, outcome)| outcome)
+                .collect()
+        };
+        let first = outcomes(&mut manifest, false);
+        assert_eq!(first[0], UninstallOutcome::Removed);
+        assert_eq!(first[1], UninstallOutcome::KeptModified);
+        assert_eq!(first[2], UninstallOutcome::Missing);
+        assert!(matches!(first[3], UninstallOutcome::Failed(_)));
+        assert!(!intact.exists() && modified.exists());
+        let kept: Vec<&Path> = manifest
+            .artifacts
+            .iter()
+            .map(|a| a.path.as_path())
+            .collect();
+        assert_eq!(kept, [modified.as_path(), blocked.as_path()]);
+
+        let second = outcomes(&mut manifest, true);
+        assert_eq!(second[0], UninstallOutcome::RemovedModified);
+        assert!(!modified.exists());
+        assert_eq!(manifest.artifacts.len(), 1);
+        assert_eq!(manifest.artifacts[0].path, blocked);
+    }
+}
@@ -1,6 +1,7 @@
 use serde_json::{json, Value};
 
 use crate::error::{AppError, AppResult};
+use crate::locale::DEFAULT_REGION;
 
 /// Patch execution result.
 ///
@@ -19,6 +20,40 @@ pub struct PatchReport {
     pub changed_variations_country: bool,
     /// Whether variations_permanent_consistency_country was modified.
     pub changed_variations_permanent_country: bool,
+    /// What happened to each field, in patch order.
+    pub fields: Vec<PatchedField>,
+}
+
+/// One field handled by [`apply_patches_with`].
+///
+/// # Examples
+///
+/// ```text
+/// PatchedField { key: "variations_country", outcome: FieldOutcome::Written(json!("us")) }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchedField {
+    /// Top-level Local State key.
+    pub key: &'static str,
+    /// What was done with it.
+    pub outcome: FieldOutcome,
+}
+
+/// What [`apply_patches_with`] did with a field.
+///
+/// # Examples
+///
+/// ```text
+/// FieldOutcome::Written(json!(["140.0.7339.80", "us"]))
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldOutcome {
+    /// The field now holds this value.
+    Written(Value),
+    /// The caller chose to leave the field alone.
+    Skipped,
+    /// The field is missing or does not have the expected type.
+    NotFound,
 }
 
 /// Apply Gemini unlock patch.
@@ -36,6 +71,26 @@ pub struct PatchReport {
 /// assert!(report.changed_is_glic);
 /// ```
 pub fn apply_patches(input: &str) -> AppResult<PatchReport> {
+    apply_patches_with(input, Some(&json!([DEFAULT_REGION.country])))
+}
+
+/// [`apply_patches`] with the value of `variations_permanent_consistency_country`
+/// chosen by the caller, e.g. from a versioned rule set; `None` leaves it alone.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::patcher::{apply_patches_with, FieldOutcome};
+/// use serde_json::json;
+///
+/// let input = r#"{"variations_permanent_consistency_country": ["140.0.7339.80", "de"]}"#;
+/// let report = apply_patches_with(input, Some(&json!(["140.0.7339.80", "us"]))).unwrap();
+/// assert!(report.content.contains(r#""us""#));
+///
+/// let report = apply_patches_with(input, None).unwrap();
+/// assert_eq!(report.fields[2].outcome, FieldOutcome::Skipped);
+/// ```
+pub fn apply_patches_with(input: &str, consistency: Option<&Value>) -> AppResult<PatchReport> {
     // 1. Parse JSON, validate input
     let mut json: Value = serde_json::from_str(input)
         .map_err(|e| AppError::InvalidJson(format!("Input JSON parsing failed: {e}")))?;
@@ -45,6 +100,10 @@ pub fn apply_patches(input: &str) -> AppResult<PatchReport> {
         changed_is_glic: false,
         changed_variations_country: false,
         changed_variations_permanent_country: false,
+        fields: Vec::new(),
+    };
+    let outcome = |changed: bool, value: Value| {
+        if changed { FieldOutcome::Written(value) } else { FieldOutcome::NotFound }
     };
 
     // 2. Ensure it's a JSON object
@@ -52,26 +111,42 @@ pub fn apply_patches(input: &str) -> AppResult<PatchReport> {
         .ok_or_else(|| AppError::InvalidJson("Config file root is not an object".to_string()))?;
 
     // 3. Safely modify is_glic_eligible
-    if let Some(value) = obj.get_mut("is_glic_eligible") {
-        if value.is_boolean() {
-            *value = json!(true);
-            report.changed_is_glic = true;
-        }
+    if let Some(value) = obj.get_mut("is_glic_eligible")
+        && value.is_boolean()
+    {
+        *value = json!(true);
+        report.changed_is_glic = true;
     }
+    report.fields.push(PatchedField {
+        key: "is_glic_eligible",
+        outcome: outcome(report.changed_is_glic, json!(true)),
+    });
 
     // 4. Safely modify variations_country
     if obj.contains_key("variations_country") {
-        obj.insert("variations_country".into(), json!("us"));
+        obj.insert("variations_country".into(), json!(DEFAULT_REGION.country));
         report.changed_variations_country = true;
     }
+    report.fields.push(PatchedField {
+        key: "variations_country",
+        outcome: outcome(report.changed_variations_country, json!(DEFAULT_REGION.country)),
+    });
 
     // 5. Safely modify variations_permanent_consistency_country
-    if let Some(arr) = obj.get_mut("variations_permanent_consistency_country") {
-        if arr.is_array() {
-            *arr = json!(["us"]);
-            report.changed_variations_permanent_country = true;
-        }
+    if let Some(consistency) = consistency
+        && let Some(arr) = obj.get_mut("variations_permanent_consistency_country")
+        && arr.is_array()
+    {
+        *arr = consistency.clone();
+        report.changed_variations_permanent_country = true;
     }
+    report.fields.push(PatchedField {
+        key: "variations_permanent_consistency_country",
+        outcome: match consistency {
+            Some(value) => outcome(report.changed_variations_permanent_country, value.clone()),
+            None => FieldOutcome::Skipped,
+        },
+    });
 
     // 6. Serialize back to JSON (auto-format and validate)
     report.content = serde_json::to_string_pretty(obj)
@@ -84,6 +159,243 @@ pub fn apply_patches(input: &str) -> AppResult<PatchReport> {
     Ok(report)
 }
 
+/// A single edit of a JSON settings file, addressed by JSON pointer.
+///
+/// # Examples
+///
+/// ```text
+/// PatchRule { pointer: "/glic/launcher_enabled", value: json!(true), create: true }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchRule {
+    /// JSON pointer of the field, e.g. `/glic/launcher_enabled`.
+    pub pointer: String,
+    /// Value to set.
+    pub value: Value,
+    /// Whether to create the field (and parent objects) if it is missing.
+    pub create: bool,
+}
+
+impl PatchRule {
+    /// Rule that sets `pointer` to `value`, creating it if missing.
+    pub fn set(pointer: &str, value: Value) -> Self {
+        Self {
+            pointer: pointer.to_string(),
+            value,
+            create: true,
+        }
+    }
+}
+
+/// One field changed by [`apply_rules`].
+///
+/// # Examples
+///
+/// ```text
+/// FieldChange { pointer: "/glic/launcher_enabled".into(), old: Some(json!(false)), new: json!(true) }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    /// JSON pointer of the field.
+    pub pointer: String,
+    /// Previous value; `None` if the field was created.
+    pub old: Option<Value>,
+    /// New value.
+    pub new: Value,
+}
+
+/// Result of applying a rule set to one file.
+///
+/// # Examples
+///
+/// ```text
+/// RuleReport { changes: vec![..], missing: vec![], unchanged: vec![..], .. }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RuleReport {
+    /// Complete content after changes.
+    pub content: String,
+    /// Fields that were modified or created.
+    pub changes: Vec<FieldChange>,
+    /// Fields that already had the target value.
+    pub unchanged: Vec<String>,
+    /// Fields that were absent and not created.
+    pub missing: Vec<String>,
+}
+
+impl RuleReport {
+    /// Whether the file needs to be written.
+    pub fn changed(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// The Local State fields [`apply_patches`] sets, as rules.
+///
+/// Fields are only changed when Chrome has already written them, so the
+/// rules do not create missing ones.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::patcher::local_state_rules;
+///
+/// assert!(local_state_rules().iter().all(|r| !r.create));
+/// ```
+pub fn local_state_rules() -> Vec<PatchRule> {
+    [
+        ("/is_glic_eligible", json!(true)),
+        ("/variations_country", json!(DEFAULT_REGION.country)),
+        ("/variations_permanent_consistency_country", json!([DEFAULT_REGION.country])),
+    ]
+    .into_iter()
+    .map(|(pointer, value)| PatchRule {
+        create: false,
+        ..PatchRule::set(pointer, value)
+    })
+    .collect()
+}
+
+/// Rules applied to each profile's `Preferences` file.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::patcher::preferences_rules;
+///
+/// assert!(preferences_rules().iter().any(|r| r.pointer == "/glic/launcher_enabled"));
+/// ```
+pub fn preferences_rules() -> Vec<PatchRule> {
+    vec![PatchRule::set("/glic/launcher_enabled", json!(true))]
+}
+
+/// Apply a rule set to a JSON document.
+///
+/// Intermediate objects are created for rules with `create`; a non-object
+/// parent is reported as missing rather than overwritten.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::patcher::{apply_rules, PatchRule};
+/// use serde_json::json;
+///
+/// let report = apply_rules("{}", &[PatchRule::set("/glic/launcher_enabled", json!(true))]).unwrap();
+/// assert!(report.changed());
+/// assert!(report.content.contains("launcher_enabled"));
+/// ```
+pub fn apply_rules(input: &str, rules: &[PatchRule]) -> AppResult<RuleReport> {
+    let mut json: Value = serde_json::from_str(input)
+        .map_err(|e| AppError::InvalidJson(format!("Input JSON parsing failed: {e}")))?;
+    if !json.is_object() {
+        return Err(AppError::InvalidJson("Config file root is not an object".to_string()));
+    }
+
+    let mut report = RuleReport::default();
+    for rule in rules {
+        match json.pointer(&rule.pointer) {
+            Some(current) if *current == rule.value => {
+                report.unchanged.push(rule.pointer.clone());
+                continue;
+            }
+            None if !rule.create => {
+                report.missing.push(rule.pointer.clone());
+                continue;
+            }
+            _ => {}
+        }
+        match set_pointer(&mut json, &rule.pointer, rule.value.clone()) {
+            Some(old) => report.changes.push(FieldChange {
+                pointer: rule.pointer.clone(),
+                old,
+                new: rule.value.clone(),
+            }),
+            None => report.missing.push(rule.pointer.clone()),
+        }
+    }
+
+    report.content = serde_json::to_string(&json)
+        .map_err(|e| AppError::InvalidJson(format!("Output JSON serialization failed: {e}")))?;
+    Ok(report)
+}
+
+/// Set the value at `pointer`, creating parent objects as needed.
+///
+/// Returns the previous value (`Some(None)` if newly created), or `None` if
+/// a parent exists but is not an object.
+pub(crate) fn set_pointer(root: &mut Value, pointer: &str, value: Value) -> Option<Option<Value>> {
+    let tokens = pointer_tokens(pointer);
+    let (last, parents) = tokens.split_last()?;
+    let mut current = root;
+    for token in parents {
+        current = current
+            .as_object_mut()?
+            .entry(token.clone())
+            .or_insert_with(|| json!({}));
+    }
+    Some(current.as_object_mut()?.insert(last.clone(), value))
+}
+
+/// Remove the value at `pointer`, returning it if it existed.
+pub(crate) fn remove_pointer(root: &mut Value, pointer: &str) -> Option<Value> {
+    let tokens = pointer_tokens(pointer);
+    let (last, parents) = tokens.split_last()?;
+    let mut current = root;
+    for token in parents {
+        current = current.as_object_mut()?.get_mut(token)?;
+    }
+    current.as_object_mut()?.remove(last)
+}
+
+/// Unescaped reference tokens of a JSON pointer.
+fn pointer_tokens(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// Mark a profile's Preferences as cleanly exited.
+///
+/// Sets `profile.exit_type` to `"Normal"` and `profile.exited_cleanly` to
+/// `true`, so Chrome restores the session without the "didn't shut down
+/// correctly" bubble. Returns `None` if the profile is already marked clean.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::patcher::mark_clean_exit;
+///
+/// let input = r#"{"profile": {"exit_type": "Crashed", "exited_cleanly": false}}"#;
+/// let output = mark_clean_exit(input).unwrap().unwrap();
+/// assert!(output.contains("Normal"));
+/// ```
+pub fn mark_clean_exit(input: &str) -> AppResult<Option<String>> {
+    let mut json: Value = serde_json::from_str(input)
+        .map_err(|e| AppError::InvalidJson(format!("Preferences parsing failed: {e}")))?;
+    let profile = json
+        .as_object_mut()
+        .ok_or_else(|| AppError::InvalidJson("Preferences root is not an object".to_string()))?
+        .entry("profile")
+        .or_insert_with(|| json!({}));
+    let profile = profile
+        .as_object_mut()
+        .ok_or_else(|| AppError::InvalidJson("Preferences profile is not an object".to_string()))?;
+
+    if profile.get("exit_type") == Some(&json!("Normal"))
+        && profile.get("exited_cleanly") == Some(&json!(true))
+    {
+        return Ok(None);
+    }
+    profile.insert("exit_type".into(), json!("Normal"));
+    profile.insert("exited_cleanly".into(), json!(true));
+
+    serde_json::to_string(&json)
+        .map(Some)
+        .map_err(|e| AppError::InvalidJson(format!("Preferences serialization failed: {e}")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,6 +477,49 @@ mod tests {
         assert_eq!(output["variations_country"], "us");
     }
 
+    #[test]
+    fn applies_rules_with_creation() {
+        let input = r#"{"glic": {"launcher_enabled": false}, "intl": "not an object"}"#;
+        let rules = vec![
+            PatchRule::set("/glic/launcher_enabled", json!(true)),
+            PatchRule::set("/a~1b/deep/value", json!(1)),
+            PatchRule::set("/intl/accept_languages", json!("en-US")),
+            PatchRule {
+                pointer: "/absent".to_string(),
+                value: json!(true),
+                create: false,
+            },
+        ];
+        let report = apply_rules(input, &rules).unwrap();
+
+        assert_eq!(report.changes.len(), 2);
+        assert_eq!(report.changes[0].old, Some(json!(false)));
+        assert_eq!(report.changes[1].old, None);
+        assert_eq!(report.missing, vec!["/intl/accept_languages", "/absent"]);
+
+        let output: Value = serde_json::from_str(&report.content).unwrap();
+        assert_eq!(output["glic"]["launcher_enabled"], true);
+        assert_eq!(output["a/b"]["deep"]["value"], 1);
+        assert_eq!(output["intl"], "not an object");
+
+        let again = apply_rules(&report.content, &rules[..2]).unwrap();
+        assert!(!again.changed());
+        assert_eq!(again.unchanged.len(), 2);
+    }
+
+    #[test]
+    fn marks_crashed_profile_clean() {
+        let input = r#"{"profile": {"exit_type": "Crashed", "exited_cleanly": false, "name": "Work"}}"#;
+        let output = mark_clean_exit(input).unwrap().expect("Should change crashed profile");
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["profile"]["exit_type"], "Normal");
+        assert_eq!(output["profile"]["exited_cleanly"], true);
+        assert_eq!(output["profile"]["name"], "Work");
+
+        let clean = r#"{"profile": {"exit_type": "Normal", "exited_cleanly": true}}"#;
+        assert!(mark_clean_exit(clean).unwrap().is_none());
+    }
+
     #[test]
     fn handles_non_boolean_is_glic() {
         // If is_glic_eligible is not boolean, should not modify
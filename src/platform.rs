@@ -1,10 +1,17 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
-use std::process::{Command, Output};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::compat::{parse_version_output, ChromeVersion};
 use crate::error::{AppError, AppResult};
+use crate::launcher::find_chrome_binary;
+use crate::policy::{POLICY_FILE, PolicyEntry, PolicyLevel};
+use crate::shutdown::{LaunchCommand, ShutdownPolicy, ShutdownReport, StageAction, StageOutcome};
+use crate::process::{
+    is_chrome_pid, list_chrome_processes, partition_processes, send_signal, ChromeChannel, ChromeProcess,
+    ProcessScope, ScopedProcesses, Signal,
+};
 
 /// Supported operating system types.
 ///
@@ -78,49 +85,259 @@ pub fn chrome_state_path(os: OsKind) -> AppResult<PathBuf> {
     }
 }
 
-/// Check if Chrome is running.
+/// Build the process scope for a user-data dir.
+///
+/// The target user is the owner of the directory (falling back to the
+/// effective UID), so running through `sudo` never reaches other users.
+/// Default user-data dirs are resolved below that user's home from the
+/// password database; `XDG_CONFIG_HOME` only counts when it is our own.
+///
+/// # Examples
+///
+/// ```text
+/// let scope = process_scope(Path::new("/home/me/.config/google-chrome"))?;
+/// ```
+pub fn process_scope(user_data_dir: &Path) -> AppResult<ProcessScope> {
+    let uid = path_owner(user_data_dir).unwrap_or_else(current_uid);
+    let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() && uid == current_uid() => PathBuf::from(dir),
+        _ => match user_home(uid) {
+            Some(home) => home.join(".config"),
+            None => {
+                let home =
+                    std::env::var("HOME").map_err(|_| AppError::MissingEnv("HOME".to_string()))?;
+                PathBuf::from(home).join(".config")
+            }
+        },
+    };
+    Ok(ProcessScope {
+        uid,
+        user_data_dir: user_data_dir.to_path_buf(),
+        config_home,
+    })
+}
+
+/// Scan Chrome processes and split them by the target user-data dir.
+///
+/// # Examples
+///
+/// ```text
+/// let scoped = scan_chrome(Path::new("/home/me/.config/google-chrome"))?;
+/// ```
+pub fn scan_chrome(user_data_dir: &Path) -> AppResult<ScopedProcesses> {
+    let scope = process_scope(user_data_dir)?;
+    Ok(partition_processes(list_chrome_processes()?, &scope))
+}
+
+/// Chrome processes that do not belong to the target and will be left alone.
+///
+/// Only Linux can attribute processes to a user-data dir; elsewhere this is empty.
+///
+/// # Examples
+///
+/// ```text
+/// let others = other_chrome_processes(OsKind::Linux, &user_data_dir)?;
+/// ```
+pub fn other_chrome_processes(os: OsKind, user_data_dir: &Path) -> AppResult<Vec<ChromeProcess>> {
+    match os {
+        OsKind::Linux => Ok(scan_chrome(user_data_dir)?.ignored),
+        OsKind::Macos | OsKind::Windows => Ok(Vec::new()),
+    }
+}
+
+/// Check if the Chrome instance using `user_data_dir` is running.
+///
+/// On macOS and Windows this is limited to the current user's Chrome.
 ///
 /// # Examples
 ///
 /// ```text
-/// let running = is_chrome_running(OsKind::Macos)?;
+/// let running = is_chrome_running(OsKind::Macos, &user_data_dir)?;
 /// ```
-pub fn is_chrome_running(os: OsKind) -> AppResult<bool> {
+pub fn is_chrome_running(os: OsKind, user_data_dir: &Path) -> AppResult<bool> {
     match os {
         OsKind::Macos => pgrep_running("Google Chrome"),
-        OsKind::Linux => {
-            if pgrep_running("chrome")? {
-                Ok(true)
-            } else {
-                pgrep_running("google-chrome")
-            }
+        OsKind::Linux => Ok(!scan_chrome(user_data_dir)?.targeted.is_empty()),
+        OsKind::Windows => Ok(!windows_chrome_pids()?.is_empty()),
+    }
+}
+
+/// Close the Chrome instance using `user_data_dir`, escalating through the
+/// stages of `policy` until it exits.
+///
+/// Chrome processes of other users or other user-data dirs are left alone;
+/// on macOS and Windows only the current user's Chrome is affected.
+///
+/// # Examples
+///
+/// ```text
+/// let report = stop_chrome(OsKind::Linux, &user_data_dir, &ShutdownPolicy::default())?;
+/// ```
+pub fn stop_chrome(
+    os: OsKind,
+    user_data_dir: &Path,
+    policy: &ShutdownPolicy,
+) -> AppResult<ShutdownReport> {
+    if os == OsKind::Linux {
+        log_ignored(&scan_chrome(user_data_dir)?.ignored);
+    }
+
+    let mut report = ShutdownReport {
+        relaunch: launch_command(os, user_data_dir)?,
+        ..ShutdownReport::default()
+    };
+    for stage in &policy.stages {
+        if !is_chrome_running(os, user_data_dir)? {
+            break;
         }
-        OsKind::Windows => {
-            let output = Command::new("tasklist")
-                .args(["/FI", "IMAGENAME eq chrome.exe"])
-                .output()?;
-            if !output.status.success() {
-                return Err(command_failed(
-                    "tasklist /FI \"IMAGENAME eq chrome.exe\"",
-                    &output,
-                ));
+        let pids = target_pids(os, user_data_dir, stage.action == StageAction::Close)?;
+        log::info!(
+            "Shutdown stage '{}': PIDs {:?}, timeout {:?}",
+            stage.action,
+            pids,
+            stage.timeout()
+        );
+        if let Err(err) = run_stage(os, stage.action, &pids) {
+            log::warn!("Shutdown stage '{}' failed: {err}", stage.action);
+        }
+
+        let start = Instant::now();
+        let stopped =
+            wait_for_process_stop(os, user_data_dir, stage.timeout(), policy.poll_interval())?;
+        report.stages.push(StageOutcome {
+            action: stage.action,
+            pids,
+            elapsed: start.elapsed(),
+            stopped,
+        });
+        if stopped {
+            return Ok(report);
+        }
+        log::warn!("Chrome still running after stage '{}'", stage.action);
+    }
+
+    if is_chrome_running(os, user_data_dir)? {
+        Err(AppError::ChromeStillRunning)
+    } else {
+        Ok(report)
+    }
+}
+
+/// Wait for the user to close the Chrome instance using `user_data_dir`.
+///
+/// `progress` is called with the elapsed time after every check. Returns
+/// `false` if `timeout` expires first; `None` waits indefinitely.
+///
+/// # Examples
+///
+/// ```text
+/// let closed = wait_for_profile_release(os, &dir, Some(Duration::from_secs(600)), poll, |_| {})?;
+/// ```
+pub fn wait_for_profile_release(
+    os: OsKind,
+    user_data_dir: &Path,
+    timeout: Option<Duration>,
+    check_interval: Duration,
+    mut progress: impl FnMut(Duration),
+) -> AppResult<bool> {
+    let start = Instant::now();
+    loop {
+        if !profile_in_use(os, user_data_dir)? {
+            return Ok(true);
+        }
+        let elapsed = start.elapsed();
+        progress(elapsed);
+        if timeout.is_some_and(|timeout| elapsed >= timeout) {
+            return Ok(false);
+        }
+        thread::sleep(check_interval);
+    }
+}
+
+/// Record how the running browser was started so it can be relaunched.
+///
+/// On Linux this is the browser's executable and original arguments; it is
+/// not recorded for another user's Chrome, since relaunching would start it
+/// as the wrong user. macOS and Windows relaunch the default Chrome.
+fn launch_command(os: OsKind, user_data_dir: &Path) -> AppResult<Option<LaunchCommand>> {
+    match os {
+        OsKind::Linux => {
+            let scoped = scan_chrome(user_data_dir)?;
+            let Some(browser) = scoped.targeted.iter().find(|p| p.is_browser()) else {
+                return Ok(None);
+            };
+            if browser.uid != current_uid() {
+                log::warn!(
+                    "Chrome PID {} belongs to uid {}, it will not be relaunched",
+                    browser.pid,
+                    browser.uid
+                );
+                return Ok(None);
             }
-            let stdout = String::from_utf8_lossy(&output.stdout).to_ascii_lowercase();
-            Ok(stdout.contains("chrome.exe"))
+            let exe = browser.exe.to_string_lossy();
+            Ok(Some(LaunchCommand {
+                program: PathBuf::from(exe.trim_end_matches(" (deleted)")),
+                args: browser.cmdline.iter().skip(1).cloned().collect(),
+            }))
         }
+        OsKind::Macos => Ok(Some(LaunchCommand {
+            program: PathBuf::from("open"),
+            args: vec!["-a".to_string(), "Google Chrome".to_string()],
+        })),
+        OsKind::Windows => Ok(Some(LaunchCommand {
+            program: PathBuf::from("cmd"),
+            args: ["/C", "start", "", "chrome"].map(String::from).to_vec(),
+        })),
     }
 }
 
-/// Close running Chrome.
+/// Start Chrome detached from this process.
 ///
 /// # Examples
 ///
 /// ```text
-/// stop_chrome(OsKind::Windows)?;
+/// relaunch_chrome(&report.relaunch.unwrap())?;
 /// ```
-pub fn stop_chrome(os: OsKind) -> AppResult<()> {
+pub fn relaunch_chrome(command: &LaunchCommand) -> AppResult<u32> {
+    let mut cmd = Command::new(&command.program);
+    cmd.args(&command.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Own process group, so it survives our terminal's signals
+        cmd.process_group(0);
+    }
+    let child = cmd.spawn().map_err(|e| AppError::CommandFailed {
+        command: command.program.display().to_string(),
+        details: e.to_string(),
+    })?;
+    Ok(child.id())
+}
+
+/// PIDs a shutdown stage should target.
+fn target_pids(os: OsKind, user_data_dir: &Path, browser_only: bool) -> AppResult<Vec<i32>> {
     match os {
-        OsKind::Macos => {
+        OsKind::Linux => Ok(scan_chrome(user_data_dir)?
+            .targeted
+            .iter()
+            .filter(|p| !browser_only || p.is_browser())
+            .map(|p| p.pid)
+            .collect()),
+        OsKind::Macos => pgrep_pids("Google Chrome"),
+        OsKind::Windows => windows_chrome_pids(),
+    }
+}
+
+/// Perform one shutdown action.
+fn run_stage(os: OsKind, action: StageAction, pids: &[i32]) -> AppResult<()> {
+    match (os, action) {
+        (OsKind::Linux, StageAction::Close | StageAction::Term) => send_signal(pids, Signal::Term),
+        (OsKind::Linux | OsKind::Macos, StageAction::Kill) => send_signal(pids, Signal::Kill),
+        (OsKind::Macos, StageAction::Term) => send_signal(pids, Signal::Term),
+        (OsKind::Macos, StageAction::Close) => {
             let output = Command::new("osascript")
                 .args(["-e", "quit app \"Google Chrome\""])
                 .output()?;
@@ -130,42 +347,436 @@ pub fn stop_chrome(os: OsKind) -> AppResult<()> {
                     &output,
                 ));
             }
+            Ok(())
         }
-        OsKind::Windows => {
-            let output = Command::new("taskkill")
-                .args(["/IM", "chrome.exe", "/F"])
-                .output()?;
+        (OsKind::Windows, action) => {
+            let mut args = vec!["/IM".to_string(), "chrome.exe".to_string()];
+            args.extend(windows_user_filter());
+            if action.is_forced() {
+                args.push("/F".to_string());
+            }
+            let output = Command::new("taskkill").args(&args).output()?;
             if !output.status.success() {
-                return Err(command_failed("taskkill /IM chrome.exe /F", &output));
+                return Err(command_failed(&format!("taskkill {}", args.join(" ")), &output));
             }
+            Ok(())
         }
-        OsKind::Linux => {
-            let pids = pgrep_pids(&["chrome", "google-chrome"])?;
-            if pids.is_empty() {
-                return Ok(());
-            }
-            send_signal(&pids, "TERM")?;
+    }
+}
+
+/// PIDs of the current user's `chrome.exe` processes.
+fn windows_chrome_pids() -> AppResult<Vec<i32>> {
+    let mut args = vec!["/FI".to_string(), "IMAGENAME eq chrome.exe".to_string()];
+    args.extend(windows_user_filter());
+    args.extend(["/FO".to_string(), "CSV".to_string(), "/NH".to_string()]);
+    let output = Command::new("tasklist").args(&args).output()?;
+    if !output.status.success() {
+        return Err(command_failed(&format!("tasklist {}", args.join(" ")), &output));
+    }
+    Ok(parse_tasklist_csv(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Extract PIDs from `tasklist /FO CSV /NH` output.
+///
+/// Lines that are not CSV rows, such as the "no tasks" notice, are skipped.
+fn parse_tasklist_csv(output: &str) -> Vec<i32> {
+    output
+        .lines()
+        .filter(|line| line.starts_with('"'))
+        .filter_map(|line| line.split(',').nth(1))
+        .filter_map(|pid| pid.trim_matches('"').parse().ok())
+        .collect()
+}
+
+/// Name of Chrome's profile lock inside the user-data dir.
+pub const SINGLETON_LOCK: &str = "SingletonLock";
+
+/// Companion files Chrome creates next to [`SINGLETON_LOCK`].
+const SINGLETON_COMPANIONS: &[&str] = &["SingletonCookie", "SingletonSocket"];
+
+/// Owner recorded in a `SingletonLock` symlink (`hostname-pid`).
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::platform::SingletonLock;
+///
+/// let lock = SingletonLock::parse("my-laptop-4242").unwrap();
+/// assert_eq!(lock.hostname, "my-laptop");
+/// assert_eq!(lock.pid, 4242);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SingletonLock {
+    /// Host that holds the lock.
+    pub hostname: String,
+    /// Browser PID on that host.
+    pub pid: i32,
+}
+
+impl SingletonLock {
+    /// Parse a symlink target of the form `hostname-pid`.
+    ///
+    /// Hostnames may contain dashes, so the PID is taken after the last one.
+    pub fn parse(target: &str) -> Option<Self> {
+        let (hostname, pid) = target.rsplit_once('-')?;
+        if hostname.is_empty() {
+            return None;
         }
+        Some(Self {
+            hostname: hostname.to_string(),
+            pid: pid.parse().ok()?,
+        })
     }
+}
 
-    // Wait for process exit (using improved waiting mechanism)
-    wait_for_process_stop(os, Duration::from_secs(3), Duration::from_millis(300))?;
+/// State of the `SingletonLock` of a user-data dir.
+///
+/// # Examples
+///
+/// ```text
+/// LockStatus::Held(SingletonLock { hostname: "my-laptop".into(), pid: 4242 })
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LockStatus {
+    /// No lock; the directory is not claimed.
+    Absent,
+    /// Held by a live Chrome on this host.
+    Held(SingletonLock),
+    /// Held by a Chrome on another host (shared home directory).
+    OtherHost(SingletonLock),
+    /// Left behind by a Chrome that no longer runs on this host.
+    Stale(SingletonLock),
+    /// Present but not in `hostname-pid` form.
+    Unreadable(String),
+}
+
+/// Read and evaluate the `SingletonLock` of a user-data dir.
+///
+/// Windows does not use a lock symlink and always reports [`LockStatus::Absent`].
+///
+/// # Examples
+///
+/// ```text
+/// let status = singleton_lock_status(OsKind::Linux, &user_data_dir)?;
+/// ```
+pub fn singleton_lock_status(os: OsKind, user_data_dir: &Path) -> AppResult<LockStatus> {
+    if os == OsKind::Windows {
+        return Ok(LockStatus::Absent);
+    }
+    let lock_path = user_data_dir.join(SINGLETON_LOCK);
+    let target = match std::fs::read_link(&lock_path) {
+        Ok(target) => target,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(LockStatus::Absent),
+        Err(err) => return Err(err.into()),
+    };
+    let target = target.to_string_lossy().into_owned();
+    Ok(evaluate_lock(&target, &local_hostname(), is_chrome_pid))
+}
 
-    // Linux special handling: if TERM signal fails, try KILL
-    if os == OsKind::Linux && is_chrome_running(os)? {
-        log::warn!("TERM signal failed, trying KILL signal");
-        let pids = pgrep_pids(&["chrome", "google-chrome"])?;
-        if !pids.is_empty() {
-            send_signal(&pids, "KILL")?;
+/// Classify a lock target against the local host and a liveness check.
+fn evaluate_lock(target: &str, hostname: &str, alive: impl Fn(i32) -> bool) -> LockStatus {
+    match SingletonLock::parse(target) {
+        None => LockStatus::Unreadable(target.to_string()),
+        Some(lock) if lock.hostname != hostname => LockStatus::OtherHost(lock),
+        Some(lock) if alive(lock.pid) => LockStatus::Held(lock),
+        Some(lock) => LockStatus::Stale(lock),
+    }
+}
+
+/// Whether the user-data dir is in use, from both the lock and the process scan.
+///
+/// # Examples
+///
+/// ```text
+/// if profile_in_use(OsKind::Linux, &user_data_dir)? { .. }
+/// ```
+pub fn profile_in_use(os: OsKind, user_data_dir: &Path) -> AppResult<bool> {
+    if matches!(
+        singleton_lock_status(os, user_data_dir)?,
+        LockStatus::Held(_) | LockStatus::OtherHost(_)
+    ) {
+        return Ok(true);
+    }
+    is_chrome_running(os, user_data_dir)
+}
+
+/// Remove a stale `SingletonLock` and its companion files.
+///
+/// Returns `false` without touching anything unless the lock is stale.
+///
+/// # Examples
+///
+/// ```text
+/// let removed = remove_stale_singleton_lock(OsKind::Linux, &user_data_dir)?;
+/// ```
+pub fn remove_stale_singleton_lock(os: OsKind, user_data_dir: &Path) -> AppResult<bool> {
+    if !matches!(singleton_lock_status(os, user_data_dir)?, LockStatus::Stale(_)) {
+        return Ok(false);
+    }
+    for name in std::iter::once(SINGLETON_LOCK).chain(SINGLETON_COMPANIONS.iter().copied()) {
+        match std::fs::remove_file(user_data_dir.join(name)) {
+            Ok(()) => log::debug!("Removed {name}"),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
         }
-        wait_for_process_stop(os, Duration::from_secs(3), Duration::from_millis(300))?;
     }
+    Ok(true)
+}
 
-    if is_chrome_running(os)? {
-        Err(AppError::ChromeStillRunning)
-    } else {
-        Ok(())
+/// File in the user-data dir holding the version of the last Chrome that ran.
+pub const LAST_VERSION_FILE: &str = "Last Version";
+
+/// Version of the Chrome that last used `user_data_dir`, e.g. `137.0.7151.68`.
+///
+/// # Examples
+///
+/// ```text
+/// if let Some(version) = last_chrome_version(&user_data_dir) { .. }
+/// ```
+pub fn last_chrome_version(user_data_dir: &Path) -> Option<String> {
+    std::fs::read_to_string(user_data_dir.join(LAST_VERSION_FILE))
+        .ok()
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+}
+
+/// Version of the Chrome using `user_data_dir`.
+///
+/// On Linux, asks the channel's binary with `--version` first: right after an
+/// update the `Last Version` file still holds the old version until Chrome
+/// starts again. Falls back to `Last Version` when no binary is found, and on
+/// other platforms.
+///
+/// # Examples
+///
+/// ```text
+/// let version = detect_chrome_version(OsKind::Linux, &user_data_dir);
+/// ```
+pub fn detect_chrome_version(os: OsKind, user_data_dir: &Path) -> Option<ChromeVersion> {
+    detect_chrome_version_in(os, user_data_dir, Path::new("/"))
+}
+
+/// [`detect_chrome_version`] with binaries looked up below `root`.
+fn detect_chrome_version_in(os: OsKind, user_data_dir: &Path, root: &Path) -> Option<ChromeVersion> {
+    if os == OsKind::Linux
+        // User-data dir names carry the channel the same way install paths do
+        && let Some(binary) = find_chrome_binary(ChromeChannel::from_exe(user_data_dir), root)
+        && let Some(version) = binary_chrome_version(&binary)
+    {
+        return Some(version);
+    }
+    let version = last_chrome_version(user_data_dir)?;
+    version
+        .parse()
+        .map_err(|err| log::warn!("{LAST_VERSION_FILE}: {err}"))
+        .ok()
+}
+
+/// Version reported by `binary --version`.
+///
+/// # Examples
+///
+/// ```text
+/// let version = binary_chrome_version(Path::new("/usr/bin/google-chrome"));
+/// ```
+pub fn binary_chrome_version(binary: &Path) -> Option<ChromeVersion> {
+    let output = Command::new(binary)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        log::debug!("{} --version failed: {:?}", binary.display(), output.status.code());
+        return None;
+    }
+    parse_version_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Linux policy directories, relative to the policy root, and their level.
+pub const POLICY_DIRS: &[(&str, PolicyLevel)] = &[
+    ("etc/opt/chrome/policies/managed", PolicyLevel::Managed),
+    ("etc/opt/chrome/policies/recommended", PolicyLevel::Recommended),
+    ("etc/chromium/policies/managed", PolicyLevel::Managed),
+    ("etc/chromium/policies/recommended", PolicyLevel::Recommended),
+];
+
+/// Path of the tool's own policy file for `level`, under Chrome's or Chromium's
+/// policy directory.
+///
+/// Only Linux reads policies from JSON files.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::platform::{policy_file_path, OsKind};
+/// use gemini_unlock::policy::PolicyLevel;
+///
+/// let path = policy_file_path(OsKind::Linux, Path::new("/"), PolicyLevel::Recommended, false).unwrap();
+/// assert_eq!(path, Path::new("/etc/opt/chrome/policies/recommended/gemini-unlock.json"));
+/// ```
+pub fn policy_file_path(
+    os: OsKind,
+    root: &Path,
+    level: PolicyLevel,
+    chromium: bool,
+) -> AppResult<PathBuf> {
+    if os != OsKind::Linux {
+        return Err(AppError::UnsupportedOs(format!(
+            "{os:?} does not read policies from JSON files"
+        )));
     }
+    let browser = if chromium { "etc/chromium/" } else { "etc/opt/chrome/" };
+    let (dir, _) = POLICY_DIRS
+        .iter()
+        .find(|(dir, dir_level)| dir.starts_with(browser) && *dir_level == level)
+        .expect("POLICY_DIRS covers both browsers and levels");
+    Ok(root.join(dir).join(POLICY_FILE))
+}
+
+/// Read every policy from the JSON files in the Linux policy directories.
+///
+/// `root` is `/` outside of tests. Files that are not valid JSON objects are
+/// skipped with a warning, as Chrome ignores them too; so are directories and
+/// files that cannot be read, so one of them does not hide the rest. Other
+/// systems keep policies in the registry or in profiles and yield nothing.
+///
+/// # Examples
+///
+/// ```text
+/// let policies = read_policies(OsKind::Linux, Path::new("/"))?;
+/// ```
+pub fn read_policies(os: OsKind, root: &Path) -> AppResult<Vec<PolicyEntry>> {
+    if os != OsKind::Linux {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for (dir, level) in POLICY_DIRS {
+        let dir = root.join(dir);
+        let mut files: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                log::warn!("Skipping unreadable policy directory {}: {err}", dir.display());
+                continue;
+            }
+        };
+        files.sort();
+        for file in files {
+            let content = match std::fs::read_to_string(&file) {
+                Ok(content) => content,
+                Err(err) => {
+                    log::warn!("Skipping unreadable policy file {}: {err}", file.display());
+                    continue;
+                }
+            };
+            let Ok(serde_json::Value::Object(policies)) = serde_json::from_str(&content) else {
+                log::warn!("Ignoring invalid policy file {}", file.display());
+                continue;
+            };
+            entries.extend(policies.into_iter().map(|(name, value)| PolicyEntry {
+                level: *level,
+                source: file.clone(),
+                name,
+                value,
+            }));
+        }
+    }
+    Ok(entries)
+}
+
+/// Host name as Chrome writes it into the lock.
+#[cfg(unix)]
+fn local_hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for its full length; gethostname NUL-terminates
+    // unless truncated, which the length bound below handles.
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if rc != 0 {
+        return String::new();
+    }
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+#[cfg(not(unix))]
+fn local_hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_default()
+}
+
+/// Log Chrome processes that are deliberately not touched.
+fn log_ignored(ignored: &[ChromeProcess]) {
+    for process in ignored.iter().filter(|p| p.is_browser()) {
+        log::debug!(
+            "Leaving Chrome process {} alone (uid {}, user-data-dir {})",
+            process.pid,
+            process.uid,
+            process
+                .user_data_dir
+                .as_deref()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_else(|| "default".to_string())
+        );
+    }
+}
+
+/// `tasklist`/`taskkill` filter restricting matches to the current user.
+fn windows_user_filter() -> Vec<String> {
+    match std::env::var("USERNAME") {
+        Ok(user) if !user.is_empty() => vec!["/FI".to_string(), format!("USERNAME eq {user}")],
+        _ => Vec::new(),
+    }
+}
+
+/// Owner UID of a path.
+#[cfg(unix)]
+fn path_owner(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|meta| meta.uid())
+}
+
+#[cfg(not(unix))]
+fn path_owner(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Home directory of a user from the password database.
+#[cfg(unix)]
+fn user_home(uid: u32) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    // SAFETY: `passwd` is plain old data that getpwuid_r fills in.
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result = std::ptr::null_mut();
+    // SAFETY: `passwd` and `buf` outlive the call and `buf.len()` is its size.
+    let rc =
+        unsafe { libc::getpwuid_r(uid, &mut passwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc != 0 || result.is_null() || passwd.pw_dir.is_null() {
+        return None;
+    }
+    // SAFETY: on success `pw_dir` points to a NUL-terminated string inside `buf`.
+    let dir = unsafe { std::ffi::CStr::from_ptr(passwd.pw_dir) };
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(dir.to_bytes())))
+}
+
+#[cfg(not(unix))]
+fn user_home(_uid: u32) -> Option<PathBuf> {
+    None
+}
+
+/// Effective UID of this process.
+#[cfg(unix)]
+fn current_uid() -> u32 {
+    // SAFETY: geteuid(2) cannot fail and has no preconditions.
+    unsafe { libc::geteuid() }
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> u32 {
+    0
 }
 
 /// Wait for process to stop
@@ -173,103 +784,116 @@ pub fn stop_chrome(os: OsKind) -> AppResult<()> {
 /// # Arguments
 ///
 /// * `os` - Operating system type
+/// * `user_data_dir` - Target user-data dir
 /// * `timeout` - Total timeout duration
 /// * `check_interval` - Check interval
-fn wait_for_process_stop(os: OsKind, timeout: Duration, check_interval: Duration) -> AppResult<()> {
-    let start = std::time::Instant::now();
+///
+/// Returns whether Chrome exited within `timeout`.
+fn wait_for_process_stop(
+    os: OsKind,
+    user_data_dir: &Path,
+    timeout: Duration,
+    check_interval: Duration,
+) -> AppResult<bool> {
+    let start = Instant::now();
     let mut attempts = 0;
 
-    while start.elapsed() < timeout {
+    loop {
         attempts += 1;
-        if !is_chrome_running(os)? {
+        if !is_chrome_running(os, user_data_dir)? {
             log::debug!("Process stopped at check #{}", attempts);
-            return Ok(());
+            return Ok(true);
+        }
+        if start.elapsed() >= timeout {
+            break;
         }
         thread::sleep(check_interval);
     }
 
     log::warn!("Wait for process stop timeout ({:?}), total attempts: {}", timeout, attempts);
-    Err(AppError::ChromeStillRunning)
+    Ok(false)
+}
+
+fn pgrep_pids(name: &str) -> AppResult<Vec<i32>> {
+    let output = Command::new("pgrep")
+        .arg("-x")
+        .arg("-U")
+        .arg(current_uid().to_string())
+        .arg(name)
+        .output()?;
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .filter_map(|pid| pid.parse().ok())
+            .collect()),
+        Some(1) => Ok(Vec::new()),
+        _ => Err(command_failed(&format!("pgrep -x \"{name}\""), &output)),
+    }
 }
 
 fn pgrep_running(name: &str) -> AppResult<bool> {
-    let status = Command::new("pgrep").arg("-x").arg(name).status()?;
+    let status = Command::new("pgrep")
+        .arg("-x")
+        .arg("-U")
+        .arg(current_uid().to_string())
+        .arg(name)
+        .status()?;
     match status.code() {
         Some(0) => Ok(true),
         Some(1) => Ok(false),
         Some(code) => Err(AppError::CommandFailed {
             command: format!("pgrep -x \"{name}\""),
-            details: format!("退出码 {code}"),
+            details: format!("Exit code {code}"),
         }),
         None => Err(AppError::CommandFailed {
             command: format!("pgrep -x \"{name}\""),
-            details: "被信号终止".to_string(),
+            details: "Terminated by signal".to_string(),
         }),
     }
 }
 
-fn pgrep_pids(names: &[&str]) -> AppResult<Vec<i32>> {
-    let mut pids = HashSet::new();
-    for name in names {
-        let output = Command::new("pgrep").arg("-x").arg(name).output()?;
-        match output.status.code() {
-            Some(0) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                for item in stdout.split_whitespace() {
-                    let pid: i32 = item.parse().map_err(|_| AppError::CommandFailed {
-                        command: format!("pgrep -x \"{name}\""),
-                        details: format!("无法解析 pid: {item}"),
-                    })?;
-                    pids.insert(pid);  // O(1) 插入，自动去重
-                }
-            }
-            Some(1) => {}
-            Some(code) => {
-                return Err(AppError::CommandFailed {
-                    command: format!("pgrep -x \"{name}\""),
-                    details: format!("退出码 {code}"),
-                });
-            }
-            None => {
-                return Err(AppError::CommandFailed {
-                    command: format!("pgrep -x \"{name}\""),
-                    details: "Terminated by signal".to_string(),
-                });
-            }
-        }
-    }
-    // Convert to sorted Vec for later use
-    let mut result: Vec<i32> = pids.into_iter().collect();
-    result.sort_unstable();
-    Ok(result)
-}
-
-fn send_signal(pids: &[i32], signal: &str) -> AppResult<()> {
-    if pids.is_empty() {
-        return Ok(());
-    }
-    let mut cmd = Command::new("kill");
-    cmd.arg(format!("-{signal}"));
-    for pid in pids {
-        cmd.arg(pid.to_string());
-    }
-    let output = cmd.output()?;
+/// Run `systemctl --user` with `args`.
+///
+/// # Examples
+///
+/// ```text
+/// systemctl_user(&["enable", "--now", "gemini-unlock.path"])?;
+/// ```
+pub fn systemctl_user(args: &[&str]) -> AppResult<()> {
+    let output = Command::new("systemctl").arg("--user").args(args).output()?;
     if output.status.success() {
         Ok(())
     } else {
         Err(command_failed(
-            &format!(
-                "kill -{signal} {}",
-                pids.iter()
-                    .map(|p| p.to_string())
-                    .collect::<Vec<_>>()
-                    .join(" ")
-            ),
+            &format!("systemctl --user {}", args.join(" ")),
             &output,
         ))
     }
 }
 
+/// Run `command` as `user` through `runuser` and wait for it, sharing our output.
+///
+/// # Examples
+///
+/// ```text
+/// run_as_user("alice", &["/usr/bin/gemini-unlock".into(), "watch".into(), "--once".into()])?;
+/// ```
+pub fn run_as_user(user: &str, command: &[String]) -> AppResult<()> {
+    let status = Command::new("runuser")
+        .args(["-u", user, "--"])
+        .args(command)
+        .stdin(Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::CommandFailed {
+            command: format!("runuser -u {user} -- {}", command.join(" ")),
+            details: format!("Exit code {:?}", status.code()),
+        })
+    }
+}
+
 fn command_failed(command: &str, output: &Output) -> AppError {
     let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
     let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
@@ -289,6 +913,66 @@ fn command_failed(command: &str, output: &Output) -> AppError {
 mod tests {
     use super::*;
 
+    #[cfg(unix)]
+    #[test]
+    fn installed_binary_wins_over_last_version() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = tempfile::TempDir::new().unwrap();
+        let user_data_dir = root.path().join("home/.config/google-chrome");
+        std::fs::create_dir_all(&user_data_dir).unwrap();
+        std::fs::write(user_data_dir.join(LAST_VERSION_FILE), "139.0.7258.154").unwrap();
+        assert_eq!(
+            detect_chrome_version_in(OsKind::Linux, &user_data_dir, root.path()).unwrap().to_string(),
+            "139.0.7258.154"
+        );
+
+        let binary = root.path().join("usr/bin/google-chrome");
+        std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        std::fs::write(&binary, "#!/bin/sh\necho 'Google Chrome 140.0.7339.80'\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(
+            detect_chrome_version_in(OsKind::Linux, &user_data_dir, root.path()).unwrap().to_string(),
+            "140.0.7339.80"
+        );
+        assert_eq!(
+            detect_chrome_version_in(OsKind::Macos, &user_data_dir, root.path()).unwrap().to_string(),
+            "139.0.7258.154"
+        );
+    }
+
+    #[test]
+    fn reads_policies_from_root() {
+        let root = tempfile::TempDir::new().unwrap();
+        let managed = root.path().join("etc/opt/chrome/policies/managed");
+        let recommended = root.path().join("etc/chromium/policies/recommended");
+        std::fs::create_dir_all(&managed).unwrap();
+        std::fs::create_dir_all(&recommended).unwrap();
+        std::fs::write(managed.join("gemini.json"), r#"{"GeminiSettings": 1}"#).unwrap();
+        std::fs::write(managed.join("broken.json"), "{").unwrap();
+        std::fs::write(managed.join("notes.txt"), "ignored").unwrap();
+        std::fs::create_dir(managed.join("unreadable.json")).unwrap();
+        std::fs::write(recommended.join("ai.json"), r#"{"GenAiDefaultSettings": 2}"#).unwrap();
+
+        let entries = read_policies(OsKind::Linux, root.path()).unwrap();
+        let names: Vec<(&str, PolicyLevel)> =
+            entries.iter().map(|e| (e.name.as_str(), e.level)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("GeminiSettings", PolicyLevel::Managed),
+                ("GenAiDefaultSettings", PolicyLevel::Recommended)
+            ]
+        );
+        assert!(read_policies(OsKind::Macos, root.path()).unwrap().is_empty());
+
+        let own = policy_file_path(OsKind::Linux, root.path(), PolicyLevel::Managed, true).unwrap();
+        assert_eq!(
+            own,
+            root.path().join("etc/chromium/policies/managed/gemini-unlock.json")
+        );
+        assert!(policy_file_path(OsKind::Windows, root.path(), PolicyLevel::Managed, true).is_err());
+    }
+
     #[test]
     fn test_os_kind_equality() {
         assert_eq!(OsKind::Macos, OsKind::Macos);
@@ -355,6 +1039,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_tasklist_csv() {
+        let output = "\"chrome.exe\",\"4242\",\"Console\",\"1\",\"120,000 K\"\r\n\
+                      \"chrome.exe\",\"4243\",\"Console\",\"1\",\"80,000 K\"\r\n";
+        assert_eq!(parse_tasklist_csv(output), vec![4242, 4243]);
+        assert!(parse_tasklist_csv("INFO: No tasks are running which match the specified criteria.").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_relaunch_chrome_spawns_detached() {
+        let command = LaunchCommand {
+            program: PathBuf::from("sh"),
+            args: vec!["-c".to_string(), "exit 0".to_string()],
+        };
+        assert!(relaunch_chrome(&command).unwrap() > 0);
+
+        let missing = LaunchCommand {
+            program: PathBuf::from("/nonexistent/chrome"),
+            args: Vec::new(),
+        };
+        assert!(matches!(
+            relaunch_chrome(&missing),
+            Err(AppError::CommandFailed { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scope_uses_owner_home_from_passwd() {
+        assert!(user_home(0).is_some_and(|home| home.is_absolute()));
+        assert_eq!(user_home(u32::MAX - 7), None);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let scope = process_scope(dir.path()).unwrap();
+        assert_eq!(scope.uid, current_uid());
+        if std::env::var_os("XDG_CONFIG_HOME").is_none_or(|dir| dir.is_empty()) {
+            assert_eq!(
+                Some(scope.config_home),
+                user_home(scope.uid).map(|home| home.join(".config"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_singleton_lock_parse() {
+        assert_eq!(
+            SingletonLock::parse("build-box-01-1234"),
+            Some(SingletonLock {
+                hostname: "build-box-01".to_string(),
+                pid: 1234
+            })
+        );
+        assert_eq!(SingletonLock::parse("nopid"), None);
+        assert_eq!(SingletonLock::parse("-1234"), None);
+        assert_eq!(SingletonLock::parse("host-abc"), None);
+    }
+
+    #[test]
+    fn test_evaluate_lock() {
+        let lock = SingletonLock {
+            hostname: "here".to_string(),
+            pid: 7,
+        };
+        assert_eq!(evaluate_lock("here-7", "here", |_| true), LockStatus::Held(lock.clone()));
+        assert_eq!(evaluate_lock("here-7", "here", |_| false), LockStatus::Stale(lock));
+        assert!(matches!(
+            evaluate_lock("there-7", "here", |_| true),
+            LockStatus::OtherHost(_)
+        ));
+        assert!(matches!(
+            evaluate_lock("garbage", "here", |_| true),
+            LockStatus::Unreadable(_)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_stale_singleton_lock() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let target = format!("{}-{}", local_hostname(), i32::MAX - 1);
+        std::os::unix::fs::symlink(&target, dir.path().join(SINGLETON_LOCK)).unwrap();
+        std::fs::write(dir.path().join("SingletonCookie"), "").unwrap();
+
+        assert!(matches!(
+            singleton_lock_status(OsKind::Linux, dir.path()).unwrap(),
+            LockStatus::Stale(_)
+        ));
+        assert!(remove_stale_singleton_lock(OsKind::Linux, dir.path()).unwrap());
+        assert!(!dir.path().join("SingletonCookie").exists());
+        assert_eq!(
+            singleton_lock_status(OsKind::Linux, dir.path()).unwrap(),
+            LockStatus::Absent
+        );
+    }
+
     #[test]
     fn test_os_kind_copy() {
         // Test OsKind implements Copy
@@ -0,0 +1,400 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde_json::{Map, Value, json};
+
+use crate::error::{AppError, AppResult};
+
+/// Name of the policy file written by `policy install`.
+pub const POLICY_FILE: &str = "gemini-unlock.json";
+
+/// Whether a policy is enforced or only a default the user can change.
+///
+/// # Examples
+///
+/// ```text
+/// PolicyLevel::Managed
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PolicyLevel {
+    /// Enforced; the user cannot change it.
+    Managed,
+    /// Default value; the user can override it in settings.
+    Recommended,
+}
+
+impl fmt::Display for PolicyLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Managed => "managed",
+            Self::Recommended => "recommended",
+        })
+    }
+}
+
+impl FromStr for PolicyLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "managed" => Ok(Self::Managed),
+            "recommended" => Ok(Self::Recommended),
+            other => Err(format!("unknown policy level: {other}")),
+        }
+    }
+}
+
+/// One policy read from a policy JSON file.
+///
+/// # Examples
+///
+/// ```text
+/// PolicyEntry { level: PolicyLevel::Managed, name: "GeminiSettings".into(), value: json!(1), .. }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolicyEntry {
+    /// Enforcement level, from the directory the file is in.
+    pub level: PolicyLevel,
+    /// File the policy was read from.
+    pub source: PathBuf,
+    /// Policy name.
+    pub name: String,
+    /// Policy value.
+    pub value: Value,
+}
+
+/// How a policy affects Gemini.
+///
+/// # Examples
+///
+/// ```text
+/// PolicyEffect::Blocks
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PolicyEffect {
+    /// Turns Gemini off; patching Local State has no effect.
+    Blocks,
+    /// Restricts some Gemini features.
+    Restricts,
+}
+
+/// A policy that affects Gemini.
+///
+/// # Examples
+///
+/// ```text
+/// PolicyFinding { entry, effect: PolicyEffect::Blocks, reason: "GeminiSettings = 1 disables Gemini in Chrome" }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolicyFinding {
+    /// The policy.
+    pub entry: PolicyEntry,
+    /// Its effect.
+    pub effect: PolicyEffect,
+    /// Human-readable explanation.
+    pub reason: String,
+}
+
+impl PolicyFinding {
+    /// Whether the policy turns Gemini off for good (managed and blocking).
+    pub fn is_enforced_block(&self) -> bool {
+        self.effect == PolicyEffect::Blocks && self.entry.level == PolicyLevel::Managed
+    }
+}
+
+/// Local State pointers whose presence means Chrome is enrolled in cloud management.
+pub const CLOUD_POLICY_HINTS: &[&str] = &[
+    "/policy/cloud_management_enrollment",
+    "/policy/cloud_policy_overrides_platform_policy",
+    "/policy/cloud_user_policy_merge",
+    "/policy/cloud_user_policy_overrides_cloud_machine_policy",
+];
+
+/// Value type a policy accepts.
+///
+/// # Examples
+///
+/// ```text
+/// PolicyType::Integer(&[0, 1])
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PolicyType {
+    /// `true` or `false`.
+    Boolean,
+    /// One of the listed integers.
+    Integer(&'static [i64]),
+}
+
+/// Schema entry of a policy `policy install` may write.
+///
+/// # Examples
+///
+/// ```text
+/// PolicySchema { name: "GeminiSettings", kind: PolicyType::Integer(&[0, 1]), summary: "..." }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PolicySchema {
+    /// Policy name.
+    pub name: &'static str,
+    /// Accepted values.
+    pub kind: PolicyType,
+    /// What the values mean.
+    pub summary: &'static str,
+}
+
+/// Gemini-related policies `policy install` knows, from Chrome's policy templates.
+pub const GEMINI_POLICY_SCHEMA: &[PolicySchema] = &[
+    PolicySchema {
+        name: "GeminiSettings",
+        kind: PolicyType::Integer(&[0, 1]),
+        summary: "0 allows Gemini in Chrome, 1 disables it",
+    },
+    PolicySchema {
+        name: "GenAiDefaultSettings",
+        kind: PolicyType::Integer(&[0, 1, 2]),
+        summary: "0 allows AI features, 1 allows them without model improvement, 2 disables them",
+    },
+    PolicySchema {
+        name: "GeminiActOnWebSettings",
+        kind: PolicyType::Integer(&[0, 1]),
+        summary: "0 allows Gemini to act on web pages, 1 disables it",
+    },
+    PolicySchema {
+        name: "BuiltInAIAPIsEnabled",
+        kind: PolicyType::Boolean,
+        summary: "whether the built-in AI APIs are available",
+    },
+    PolicySchema {
+        name: "BrowserSignin",
+        kind: PolicyType::Integer(&[0, 1, 2]),
+        summary: "0 disables sign-in, 1 allows it, 2 forces it",
+    },
+];
+
+/// Check a policy name and value against [`GEMINI_POLICY_SCHEMA`].
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::policy::validate_policy;
+/// use serde_json::json;
+///
+/// assert!(validate_policy("GeminiSettings", &json!(0)).is_ok());
+/// assert!(validate_policy("GeminiSettings", &json!(true)).is_err());
+/// assert!(validate_policy("HomepageLocation", &json!("x")).is_err());
+/// ```
+pub fn validate_policy(name: &str, value: &Value) -> AppResult<()> {
+    let schema = GEMINI_POLICY_SCHEMA
+        .iter()
+        .find(|schema| schema.name == name)
+        .ok_or_else(|| AppError::InvalidPolicy(format!("unknown policy {name}")))?;
+    let valid = match schema.kind {
+        PolicyType::Boolean => value.is_boolean(),
+        PolicyType::Integer(allowed) => value.as_i64().is_some_and(|v| allowed.contains(&v)),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidPolicy(format!(
+            "{name} = {value} is not allowed ({})",
+            schema.summary
+        )))
+    }
+}
+
+/// Policies `policy install` writes unless overridden: everything Gemini needs allowed.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::policy::default_policies;
+/// use serde_json::json;
+///
+/// assert_eq!(default_policies()["GeminiSettings"], json!(0));
+/// ```
+pub fn default_policies() -> Map<String, Value> {
+    let Value::Object(policies) = json!({
+        "GeminiSettings": 0,
+        "GenAiDefaultSettings": 0,
+        "GeminiActOnWebSettings": 0,
+        "BuiltInAIAPIsEnabled": true,
+    }) else {
+        unreachable!("json! object literal")
+    };
+    policies
+}
+
+/// Content of the policy file: the defaults with `overrides` applied, validated.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::policy::policy_file_content;
+/// use serde_json::json;
+///
+/// let content = policy_file_content(&[("GeminiActOnWebSettings".into(), json!(1))]).unwrap();
+/// assert!(content.contains(r#""GeminiActOnWebSettings": 1"#));
+/// assert!(policy_file_content(&[("GeminiSettings".into(), json!(7))]).is_err());
+/// ```
+pub fn policy_file_content(overrides: &[(String, Value)]) -> AppResult<String> {
+    let mut policies = default_policies();
+    for (name, value) in overrides {
+        policies.insert(name.clone(), value.clone());
+    }
+    for (name, value) in &policies {
+        validate_policy(name, value)?;
+    }
+    serde_json::to_string_pretty(&policies)
+        .map_err(|e| AppError::InvalidJson(format!("Policy serialization failed: {e}")))
+}
+
+/// Effect of a known Gemini-related policy value, with an explanation.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::policy::{gemini_policy_effect, PolicyEffect};
+/// use serde_json::json;
+///
+/// let (effect, _) = gemini_policy_effect("GeminiSettings", &json!(1)).unwrap();
+/// assert_eq!(effect, PolicyEffect::Blocks);
+/// assert!(gemini_policy_effect("GeminiSettings", &json!(0)).is_none());
+/// ```
+pub fn gemini_policy_effect(name: &str, value: &Value) -> Option<(PolicyEffect, String)> {
+    let int = value.as_i64();
+    let flag = value.as_bool();
+    let (effect, reason) = match name {
+        "GeminiSettings" if int == Some(1) => (PolicyEffect::Blocks, "disables Gemini in Chrome"),
+        "GenAiDefaultSettings" if int == Some(2) => {
+            (PolicyEffect::Blocks, "disables all generative AI features")
+        }
+        "BrowserSignin" if int == Some(0) => (
+            PolicyEffect::Blocks,
+            "disables sign-in, which Gemini requires",
+        ),
+        "GeminiActOnWebSettings" if int == Some(1) => (
+            PolicyEffect::Restricts,
+            "disables Gemini acting on web pages",
+        ),
+        "GenAiDefaultSettings" if int == Some(1) => (
+            PolicyEffect::Restricts,
+            "allows AI features without model improvement",
+        ),
+        "BuiltInAIAPIsEnabled" if flag == Some(false) => {
+            (PolicyEffect::Restricts, "disables the built-in AI APIs")
+        }
+        _ => return None,
+    };
+    Some((effect, format!("{name} = {value} {reason}")))
+}
+
+/// Policies that affect Gemini, managed ones first.
+///
+/// # Examples
+///
+/// ```text
+/// let findings = gemini_findings(&read_policies(os, root)?);
+/// ```
+pub fn gemini_findings(entries: &[PolicyEntry]) -> Vec<PolicyFinding> {
+    let mut findings: Vec<PolicyFinding> = entries
+        .iter()
+        .filter_map(|entry| {
+            gemini_policy_effect(&entry.name, &entry.value).map(|(effect, reason)| PolicyFinding {
+                entry: entry.clone(),
+                effect,
+                reason,
+            })
+        })
+        .collect();
+    findings.sort_by_key(|finding| finding.entry.level != PolicyLevel::Managed);
+    findings
+}
+
+/// Cloud-management hints present in Local State.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::policy::cloud_policy_hints;
+/// use serde_json::json;
+///
+/// let local_state = json!({"policy": {"cloud_management_enrollment": {"mandatory": true}}});
+/// assert_eq!(cloud_policy_hints(&local_state), vec!["policy.cloud_management_enrollment"]);
+/// ```
+pub fn cloud_policy_hints(local_state: &Value) -> Vec<String> {
+    CLOUD_POLICY_HINTS
+        .iter()
+        .filter(|pointer| local_state.pointer(pointer).is_some())
+        .map(|pointer| pointer.trim_start_matches('/').replace('/', "."))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(level: PolicyLevel, name: &str, value: Value) -> PolicyEntry {
+        PolicyEntry {
+            level,
+            source: PathBuf::from("/etc/opt/chrome/policies/x.json"),
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn finds_gemini_policies_managed_first() {
+        let entries = vec![
+            entry(PolicyLevel::Recommended, "GenAiDefaultSettings", json!(1)),
+            entry(
+                PolicyLevel::Managed,
+                "HomepageLocation",
+                json!("https://example.com"),
+            ),
+            entry(PolicyLevel::Managed, "GeminiSettings", json!(1)),
+        ];
+        let findings = gemini_findings(&entries);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].entry.name, "GeminiSettings");
+        assert!(findings[0].is_enforced_block());
+        assert_eq!(findings[1].effect, PolicyEffect::Restricts);
+        assert!(!findings[1].is_enforced_block());
+    }
+
+    #[test]
+    fn ignores_allowing_values() {
+        assert!(gemini_policy_effect("GeminiSettings", &json!(0)).is_none());
+        assert!(gemini_policy_effect("GenAiDefaultSettings", &json!(0)).is_none());
+        assert!(gemini_policy_effect("BrowserSignin", &json!(1)).is_none());
+    }
+
+    #[test]
+    fn written_policies_allow_gemini() {
+        let content = policy_file_content(&[("BrowserSignin".into(), json!(1))]).unwrap();
+        let policies: Value = serde_json::from_str(&content).unwrap();
+        let Value::Object(policies) = policies else {
+            panic!("policy file is not an object")
+        };
+        assert_eq!(policies.len(), 5);
+        for (name, value) in &policies {
+            assert!(gemini_policy_effect(name, value).is_none(), "{name} blocks");
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_types_and_values() {
+        assert!(validate_policy("BuiltInAIAPIsEnabled", &json!(1)).is_err());
+        assert!(validate_policy("GenAiDefaultSettings", &json!("0")).is_err());
+        assert!(validate_policy("GenAiDefaultSettings", &json!(3)).is_err());
+        assert!(validate_policy("BrowserSignin", &json!(2)).is_ok());
+        assert_eq!("Managed".parse::<PolicyLevel>(), Ok(PolicyLevel::Managed));
+        assert!("forced".parse::<PolicyLevel>().is_err());
+    }
+
+    #[test]
+    fn no_hints_without_cloud_policy() {
+        assert!(cloud_policy_hints(&json!({"policy": {"last_statistics_update": 1}})).is_empty());
+    }
+}
@@ -0,0 +1,577 @@
+use std::fmt;
+use std::fmt::Write as _;
+
+use hmac::{Hmac, Mac};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+
+use crate::error::{AppError, AppResult};
+
+/// Secure Preferences file name inside a profile directory.
+pub const SECURE_PREFERENCES_FILE: &str = "Secure Preferences";
+
+/// JSON pointer of the per-pref MAC tree.
+const MACS_POINTER: &str = "/protection/macs";
+
+/// JSON pointer of the MAC over the whole MAC tree.
+const SUPER_MAC_POINTER: &str = "/protection/super_mac";
+
+/// Computes Chrome's tracked-preference MACs.
+///
+/// Each MAC is `HMAC-SHA256(seed, device_id + path + value)` in upper-case
+/// hex, where `value` is the pref serialized the way Chrome's JSON writer
+/// does it. The default hasher uses an empty seed and device ID.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::prefhash::PrefHasher;
+/// use serde_json::json;
+///
+/// let hasher = PrefHasher::default();
+/// let mac = hasher.mac("homepage", Some(&json!("https://example.com")));
+/// assert_eq!(mac.len(), 64);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PrefHasher {
+    seed: Vec<u8>,
+    device_id: String,
+}
+
+impl PrefHasher {
+    /// Create a hasher from a raw seed and a device ID.
+    pub fn new(seed: Vec<u8>, device_id: impl Into<String>) -> Self {
+        Self {
+            seed,
+            device_id: device_id.into(),
+        }
+    }
+
+    /// Create a hasher from a hex-encoded seed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gemini_unlock::prefhash::PrefHasher;
+    ///
+    /// assert!(PrefHasher::from_hex_seed("00ff", "").is_ok());
+    /// assert!(PrefHasher::from_hex_seed("xyz", "").is_err());
+    /// ```
+    pub fn from_hex_seed(seed: &str, device_id: impl Into<String>) -> AppResult<Self> {
+        Ok(Self::new(decode_hex(seed)?, device_id))
+    }
+
+    /// MAC of a pref at a dotted `path`; a missing pref hashes as an empty value.
+    pub fn mac(&self, path: &str, value: Option<&Value>) -> String {
+        self.hmac(&self.device_id, path, value)
+    }
+
+    /// MAC from Chrome versions that did not mix in the device ID.
+    pub fn legacy_mac(&self, path: &str, value: Option<&Value>) -> String {
+        self.hmac("", path, value)
+    }
+
+    /// MAC over the whole `protection.macs` tree.
+    pub fn super_mac(&self, macs: &Value) -> String {
+        self.mac("", Some(macs))
+    }
+
+    /// Check a stored MAC against a pref value.
+    pub fn check(&self, path: &str, value: Option<&Value>, stored: &str) -> MacStatus {
+        if stored.eq_ignore_ascii_case(&self.mac(path, value)) {
+            MacStatus::Valid
+        } else if stored.eq_ignore_ascii_case(&self.legacy_mac(path, value)) {
+            MacStatus::ValidLegacy
+        } else {
+            MacStatus::Invalid
+        }
+    }
+
+    fn hmac(&self, device_id: &str, path: &str, value: Option<&Value>) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.seed).expect("HMAC accepts keys of any length");
+        mac.update(device_id.as_bytes());
+        mac.update(path.as_bytes());
+        mac.update(value.map(value_as_string).unwrap_or_default().as_bytes());
+        encode_hex(&mac.finalize().into_bytes())
+    }
+}
+
+/// Result of checking one MAC.
+///
+/// # Examples
+///
+/// ```text
+/// MacStatus::Invalid // Chrome resets the pref on next launch
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MacStatus {
+    /// Matches the current value.
+    Valid,
+    /// Matches a MAC computed without the device ID; Chrome still accepts it.
+    ValidLegacy,
+    /// Does not match; Chrome would reset the pref.
+    Invalid,
+}
+
+impl MacStatus {
+    /// Whether Chrome accepts the MAC.
+    pub fn is_accepted(self) -> bool {
+        self != Self::Invalid
+    }
+}
+
+impl fmt::Display for MacStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Valid => "valid",
+            Self::ValidLegacy => "valid (legacy)",
+            Self::Invalid => "invalid",
+        })
+    }
+}
+
+/// MAC check of one protected pref.
+///
+/// # Examples
+///
+/// ```text
+/// MacCheck { path: "homepage".into(), status: MacStatus::Valid }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacCheck {
+    /// Dotted pref path, e.g. `extensions.settings.<id>`.
+    pub path: String,
+    /// Check result.
+    pub status: MacStatus,
+}
+
+/// MAC checks of a preferences file.
+///
+/// # Examples
+///
+/// ```text
+/// let report = verify_macs(&secure, &[&preferences], &hasher);
+/// for check in report.rejected() { ... }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MacReport {
+    /// Every pref listed in `protection.macs`, in path order.
+    pub prefs: Vec<MacCheck>,
+    /// The `super_mac` check, or `None` if the file has none.
+    pub super_mac: Option<MacStatus>,
+}
+
+impl MacReport {
+    /// Prefs Chrome would reset.
+    pub fn rejected(&self) -> impl Iterator<Item = &MacCheck> {
+        self.prefs
+            .iter()
+            .filter(|check| !check.status.is_accepted())
+    }
+
+    /// Whether Chrome accepts every MAC, including `super_mac`.
+    pub fn all_accepted(&self) -> bool {
+        self.rejected().next().is_none() && self.super_mac.is_none_or(MacStatus::is_accepted)
+    }
+}
+
+/// Check every MAC in `store`'s `protection.macs`.
+///
+/// Pref values are looked up in `store` first, then in `others` (the
+/// regular `Preferences` holds values protected from `Secure Preferences`).
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::prefhash::{update_macs, verify_macs, PrefHasher};
+/// use serde_json::json;
+///
+/// let hasher = PrefHasher::default();
+/// let mut secure = json!({"homepage": "https://example.com"});
+/// update_macs(&mut secure, &[], &["homepage".to_string()], &hasher).unwrap();
+/// assert!(verify_macs(&secure, &[], &hasher).all_accepted());
+///
+/// secure["homepage"] = json!("https://changed.example");
+/// assert_eq!(verify_macs(&secure, &[], &hasher).rejected().count(), 1);
+/// ```
+pub fn verify_macs(store: &Value, others: &[&Value], hasher: &PrefHasher) -> MacReport {
+    let mut macs = Vec::new();
+    if let Some(tree) = store.pointer(MACS_POINTER) {
+        collect_macs(tree, String::new(), &mut macs);
+    }
+    let prefs = macs
+        .into_iter()
+        .map(|(path, stored)| {
+            let status = hasher.check(&path, lookup(store, others, &path), &stored);
+            MacCheck { path, status }
+        })
+        .collect();
+
+    let super_mac = store
+        .pointer(SUPER_MAC_POINTER)
+        .and_then(Value::as_str)
+        .filter(|stored| !stored.is_empty())
+        .map(|stored| {
+            let empty = Value::Object(Map::new());
+            let tree = store.pointer(MACS_POINTER).unwrap_or(&empty);
+            if stored.eq_ignore_ascii_case(&hasher.super_mac(tree)) {
+                MacStatus::Valid
+            } else {
+                MacStatus::Invalid
+            }
+        });
+
+    MacReport { prefs, super_mac }
+}
+
+/// Recompute the MACs of edited prefs and the `super_mac` in `store`.
+///
+/// `paths` are dotted pref paths; values are looked up as in [`verify_macs`].
+///
+/// # Examples
+///
+/// See [`verify_macs`].
+pub fn update_macs(
+    store: &mut Value,
+    others: &[&Value],
+    paths: &[String],
+    hasher: &PrefHasher,
+) -> AppResult<()> {
+    let macs: Vec<(&String, String)> = paths
+        .iter()
+        .map(|path| (path, hasher.mac(path, lookup(store, others, path))))
+        .collect();
+
+    let root = store
+        .as_object_mut()
+        .ok_or_else(|| AppError::InvalidJson("Preferences root is not an object".to_string()))?;
+    let protection = child_object(root, "protection")?;
+    let tree = child_object(protection, "macs")?;
+    for (path, mac) in macs {
+        let (parents, leaf) = match path.rsplit_once('.') {
+            Some((parents, leaf)) => (parents.split('.').collect(), leaf),
+            None => (Vec::new(), path.as_str()),
+        };
+        let mut node = &mut *tree;
+        for key in parents {
+            node = child_object(node, key)?;
+        }
+        node.insert(leaf.to_string(), Value::String(mac));
+    }
+
+    let super_mac = hasher.super_mac(&Value::Object(tree.clone()));
+    protection.insert("super_mac".to_string(), Value::String(super_mac));
+    Ok(())
+}
+
+/// Whether `store`'s `protection.macs` holds a MAC for the dotted pref `path`.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::prefhash::is_tracked;
+/// use serde_json::json;
+///
+/// let secure = json!({"protection": {"macs": {"glic": {"launcher_enabled": "AB12"}}}});
+/// assert!(is_tracked(&secure, "glic.launcher_enabled"));
+/// assert!(!is_tracked(&secure, "intl.accept_languages"));
+/// ```
+pub fn is_tracked(store: &Value, path: &str) -> bool {
+    store
+        .pointer(MACS_POINTER)
+        .and_then(|tree| path.split('.').try_fold(tree, |node, key| node.get(key)))
+        .is_some_and(Value::is_string)
+}
+
+/// Convert a JSON pointer such as `/glic/launcher_enabled` to a dotted pref path.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::prefhash::pref_path;
+///
+/// assert_eq!(pref_path("/glic/launcher_enabled"), "glic.launcher_enabled");
+/// ```
+pub fn pref_path(pointer: &str) -> String {
+    pointer
+        .trim_start_matches('/')
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Flatten the MAC tree into `(dotted path, mac)` pairs.
+fn collect_macs(node: &Value, path: String, out: &mut Vec<(String, String)>) {
+    match node {
+        Value::String(mac) => out.push((path, mac.clone())),
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                collect_macs(child, child_path, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Value of a dotted pref path in `store`, then in `others`.
+fn lookup<'a>(store: &'a Value, others: &[&'a Value], path: &str) -> Option<&'a Value> {
+    std::iter::once(store)
+        .chain(others.iter().copied())
+        .find_map(|root| path.split('.').try_fold(root, |node, key| node.get(key)))
+}
+
+/// Get or create an object member, failing if it holds something else.
+fn child_object<'a>(
+    map: &'a mut Map<String, Value>,
+    key: &str,
+) -> AppResult<&'a mut Map<String, Value>> {
+    map.entry(key)
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| AppError::InvalidJson(format!("{key} is not an object")))
+}
+
+/// Serialize a pref value as Chrome's `JSONStringValueSerializer` does.
+///
+/// Dictionaries drop empty dictionary and list members first, keys are
+/// sorted, and strings use Chrome's escaping (`<` becomes `\u003C`).
+fn value_as_string(value: &Value) -> String {
+    let mut out = String::new();
+    match value {
+        Value::Object(map) => write_value(&Value::Object(without_empty(map)), &mut out),
+        _ => write_value(value, &mut out),
+    }
+    out
+}
+
+/// Recursively remove empty dictionaries and lists from a dictionary.
+fn without_empty(map: &Map<String, Value>) -> Map<String, Value> {
+    map.iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::Object(child) => Value::Object(without_empty(child)),
+                other => other.clone(),
+            };
+            let empty = match &value {
+                Value::Object(child) => child.is_empty(),
+                Value::Array(items) => items.is_empty(),
+                _ => false,
+            };
+            (!empty).then(|| (key.clone(), value))
+        })
+        .collect()
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => match n.as_i64().filter(|i| i32::try_from(*i).is_ok()) {
+            Some(i) => out.push_str(&i.to_string()),
+            // Chrome stores anything outside int32 as a double
+            None => write_double(n.as_f64().unwrap_or_default(), out),
+        },
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(&map[key], out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_double(value: f64, out: &mut String) {
+    let mut real = value.to_string();
+    if !real.contains(['.', 'e', 'E']) {
+        real.push_str(".0");
+    }
+    out.push_str(&real);
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '<' | '\u{2028}' | '\u{2029}' => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut out, b| {
+            let _ = write!(out, "{b:02X}");
+            out
+        })
+}
+
+fn decode_hex(hex: &str) -> AppResult<Vec<u8>> {
+    let hex = hex.trim();
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(AppError::InvalidSeed(hex.to_string()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .map_err(|_| AppError::InvalidSeed(hex.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn hmac_matches_rfc4231() {
+        // RFC 4231 test case 2
+        let hasher = PrefHasher::new(b"Jefe".to_vec(), "what do ya want ");
+        assert_eq!(
+            hasher.hmac("what do ya want ", "for nothing?", None),
+            "5BDCC146BF60754E6A042426089575C75A003F089D2739839DEC58B964EC3843"
+        );
+    }
+
+    #[test]
+    fn serializes_like_chrome() {
+        let value = json!({
+            "b": [1, 2.5, 3000000000u64],
+            "a": "<tag>\u{1}",
+            "empty": {},
+            "list": [],
+            "nested": {"gone": {}, "kept": true}
+        });
+        assert_eq!(
+            value_as_string(&value),
+            r#"{"a":"\u003Ctag>\u0001","b":[1,2.5,3000000000.0],"nested":{"kept":true}}"#
+        );
+        assert_eq!(value_as_string(&json!(null)), "null");
+    }
+
+    // Vectors computed independently with Python's hmac module
+    #[test]
+    fn computes_pref_macs() {
+        let hasher = PrefHasher::default();
+        assert_eq!(
+            hasher.mac("homepage", Some(&json!("https://example.com"))),
+            "6E8814911951966E2A37DBDFD4C85D0865C13DE49E1D57D108E2A02412DA2330"
+        );
+        assert_eq!(
+            hasher.mac("session.restore_on_startup", None),
+            "F9BD26F5D1AA6AB5258754888529CB2A82AE68D1703BCC2A97DEAEE5DDDA190E"
+        );
+
+        let seeded =
+            PrefHasher::from_hex_seed("000102030405060708090a0b0c0d0e0f", "S-1-5-21-1").unwrap();
+        assert_eq!(
+            seeded.mac(
+                "extensions.settings.abc",
+                Some(&json!({"state": 1, "path": "<x>"}))
+            ),
+            "792DE54976B205E15717A153980B5CE916B95F862A9F1F5DF72451D138DE2E77"
+        );
+        assert_eq!(
+            seeded.legacy_mac("homepage_is_newtabpage", Some(&json!(false))),
+            "09C026D925A056469721BFF1A03A191302118B5F7F42E3AAF58517AFDAB7D581"
+        );
+    }
+
+    #[test]
+    fn verifies_and_updates_macs() {
+        let hasher = PrefHasher::from_hex_seed("abcd", "device").unwrap();
+        let preferences = json!({"browser": {"show_home_button": true}});
+        let mut secure = json!({"homepage": "https://example.com"});
+        let paths = vec![
+            "homepage".to_string(),
+            "browser.show_home_button".to_string(),
+        ];
+        update_macs(&mut secure, &[&preferences], &paths, &hasher).unwrap();
+
+        let report = verify_macs(&secure, &[&preferences], &hasher);
+        assert!(report.all_accepted());
+        assert_eq!(report.prefs.len(), 2);
+        assert_eq!(report.super_mac, Some(MacStatus::Valid));
+
+        // Editing a value without resealing is rejected
+        secure["homepage"] = json!("https://changed.example");
+        let report = verify_macs(&secure, &[&preferences], &hasher);
+        let rejected: Vec<&str> = report.rejected().map(|c| c.path.as_str()).collect();
+        assert_eq!(rejected, vec!["homepage"]);
+
+        // Tampering with the MAC tree breaks super_mac
+        update_macs(
+            &mut secure,
+            &[&preferences],
+            &["homepage".to_string()],
+            &hasher,
+        )
+        .unwrap();
+        secure["protection"]["macs"]["extra"] = json!("00");
+        let report = verify_macs(&secure, &[&preferences], &hasher);
+        assert_eq!(report.super_mac, Some(MacStatus::Invalid));
+    }
+
+    #[test]
+    fn accepts_legacy_macs() {
+        let hasher = PrefHasher::new(Vec::new(), "device");
+        let value = json!(true);
+        let legacy = hasher.legacy_mac("a", Some(&value));
+        assert_eq!(
+            hasher.check("a", Some(&value), &legacy),
+            MacStatus::ValidLegacy
+        );
+        assert_eq!(
+            hasher.check("a", Some(&json!(false)), &legacy),
+            MacStatus::Invalid
+        );
+    }
+
+    #[test]
+    fn rejects_bad_seeds() {
+        assert!(matches!(
+            PrefHasher::from_hex_seed("abc", ""),
+            Err(AppError::InvalidSeed(_))
+        ));
+    }
+}
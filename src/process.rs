@@ -0,0 +1,716 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::{AppError, AppResult};
+
+/// Default procfs mount point.
+pub const PROC_ROOT: &str = "/proc";
+
+/// Executable names that identify a Chrome or Chromium process.
+///
+/// `comm` is truncated to 15 bytes by the kernel, hence `chromium-browse`.
+const CHROME_NAMES: &[&str] = &[
+    "chrome",
+    "google-chrome",
+    "google-chrome-stable",
+    "google-chrome-beta",
+    "google-chrome-unstable",
+    "chromium",
+    "chromium-browser",
+    "chromium-browse",
+];
+
+/// Chrome release channel, derived from the install location.
+///
+/// # Examples
+///
+/// ```text
+/// ChromeChannel::Stable
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChromeChannel {
+    /// Google Chrome stable.
+    Stable,
+    /// Google Chrome beta.
+    Beta,
+    /// Google Chrome dev (`chrome-unstable`).
+    Dev,
+    /// Google Chrome canary.
+    Canary,
+    /// Chromium.
+    Chromium,
+    /// Could not be determined from the executable path.
+    Unknown,
+}
+
+impl ChromeChannel {
+    /// Guess the channel from an executable path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gemini_unlock::process::ChromeChannel;
+    ///
+    /// let channel = ChromeChannel::from_exe("/opt/google/chrome-beta/chrome".as_ref());
+    /// assert_eq!(channel, ChromeChannel::Beta);
+    /// ```
+    pub fn from_exe(exe: &Path) -> Self {
+        let path = exe.to_string_lossy().to_ascii_lowercase();
+        if path.contains("chromium") {
+            Self::Chromium
+        } else if path.contains("chrome-beta") {
+            Self::Beta
+        } else if path.contains("chrome-unstable") || path.contains("chrome-dev") {
+            Self::Dev
+        } else if path.contains("canary") {
+            Self::Canary
+        } else if path.contains("chrome") {
+            Self::Stable
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+impl FromStr for ChromeChannel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "stable" => Ok(Self::Stable),
+            "beta" => Ok(Self::Beta),
+            "dev" | "unstable" => Ok(Self::Dev),
+            "canary" => Ok(Self::Canary),
+            "chromium" => Ok(Self::Chromium),
+            other => Err(format!("unknown Chrome channel: {other}")),
+        }
+    }
+}
+
+/// Role of a Chrome process.
+///
+/// # Examples
+///
+/// ```text
+/// ProcessKind::Browser
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProcessKind {
+    /// The main browser process that owns the user-data dir.
+    Browser,
+    /// Renderer, GPU, utility, zygote or crashpad helper (`--type=...`).
+    Helper,
+}
+
+/// A running Chrome process discovered through procfs.
+///
+/// # Examples
+///
+/// ```text
+/// ChromeProcess { pid: 4242, uid: 1000, kind: ProcessKind::Browser, .. }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChromeProcess {
+    /// Process ID.
+    pub pid: i32,
+    /// Parent process ID.
+    pub ppid: i32,
+    /// Real user ID of the process owner.
+    pub uid: u32,
+    /// Executable path (from `exe`, falling back to `argv[0]`).
+    pub exe: PathBuf,
+    /// Release channel guessed from `exe`.
+    pub channel: ChromeChannel,
+    /// Explicit `--user-data-dir` argument, if any, made absolute against the
+    /// process's working directory.
+    pub user_data_dir: Option<PathBuf>,
+    /// Browser or helper process.
+    pub kind: ProcessKind,
+    /// Full command line.
+    pub cmdline: Vec<String>,
+}
+
+impl ChromeProcess {
+    /// Whether this is the main browser process.
+    pub fn is_browser(&self) -> bool {
+        self.kind == ProcessKind::Browser
+    }
+
+    /// User-data dir this process uses: the explicit argument, or the
+    /// channel default below `config_home`.
+    ///
+    /// # Examples
+    ///
+    /// ```text
+    /// let dir = process.resolved_user_data_dir(Path::new("/home/me/.config"));
+    /// ```
+    pub fn resolved_user_data_dir(&self, config_home: &Path) -> PathBuf {
+        self.user_data_dir
+            .clone()
+            .unwrap_or_else(|| default_user_data_dir(self.channel, config_home))
+    }
+}
+
+/// Default Linux user-data dir of a channel below `config_home`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::process::{default_user_data_dir, ChromeChannel};
+///
+/// let dir = default_user_data_dir(ChromeChannel::Beta, Path::new("/home/me/.config"));
+/// assert_eq!(dir, Path::new("/home/me/.config/google-chrome-beta"));
+/// ```
+pub fn default_user_data_dir(channel: ChromeChannel, config_home: &Path) -> PathBuf {
+    let name = match channel {
+        ChromeChannel::Stable | ChromeChannel::Unknown => "google-chrome",
+        ChromeChannel::Beta => "google-chrome-beta",
+        ChromeChannel::Dev => "google-chrome-unstable",
+        ChromeChannel::Canary => "google-chrome-canary",
+        ChromeChannel::Chromium => "chromium",
+    };
+    config_home.join(name)
+}
+
+/// The Chrome instance an operation is allowed to touch.
+///
+/// # Examples
+///
+/// ```text
+/// ProcessScope { uid: 1000, user_data_dir: "/home/me/.config/google-chrome".into(), .. }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProcessScope {
+    /// Owner of the target user-data dir.
+    pub uid: u32,
+    /// User-data dir being patched.
+    pub user_data_dir: PathBuf,
+    /// Config home used to resolve default user-data dirs.
+    pub config_home: PathBuf,
+}
+
+/// Chrome processes split by whether they belong to the target scope.
+///
+/// # Examples
+///
+/// ```text
+/// let scoped = partition_processes(processes, &scope);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScopedProcesses {
+    /// Processes of the target user and user-data dir.
+    pub targeted: Vec<ChromeProcess>,
+    /// Everything else, which must be left alone.
+    pub ignored: Vec<ChromeProcess>,
+}
+
+impl ScopedProcesses {
+    /// PIDs of the targeted processes.
+    pub fn targeted_pids(&self) -> Vec<i32> {
+        self.targeted.iter().map(|p| p.pid).collect()
+    }
+}
+
+/// Split processes into those belonging to `scope` and the rest.
+///
+/// Helpers are attributed to their nearest browser ancestor, since zygote
+/// and renderer command lines usually omit `--user-data-dir`.
+///
+/// # Examples
+///
+/// ```text
+/// let scoped = partition_processes(list_chrome_processes()?, &scope);
+/// ```
+pub fn partition_processes(processes: Vec<ChromeProcess>, scope: &ProcessScope) -> ScopedProcesses {
+    let target_dir = normalize_path(&scope.user_data_dir);
+    let matches: Vec<bool> = processes
+        .iter()
+        .map(|process| {
+            let owner = browser_ancestor(process, &processes);
+            process.uid == scope.uid
+                && normalize_path(&owner.resolved_user_data_dir(&scope.config_home)) == target_dir
+        })
+        .collect();
+
+    let mut scoped = ScopedProcesses::default();
+    for (process, matched) in processes.into_iter().zip(matches) {
+        if matched {
+            scoped.targeted.push(process);
+        } else {
+            scoped.ignored.push(process);
+        }
+    }
+    scoped
+}
+
+/// Nearest browser process above `process`, or `process` itself.
+fn browser_ancestor<'a>(process: &'a ChromeProcess, all: &'a [ChromeProcess]) -> &'a ChromeProcess {
+    let mut current = process;
+    // Bounded walk guards against PID reuse creating a cycle
+    for _ in 0..16 {
+        if current.is_browser() {
+            break;
+        }
+        match all.iter().find(|p| p.pid == current.ppid) {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+    current
+}
+
+/// Canonicalize a path if possible so symlinked and relative dirs compare equal.
+fn normalize_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// POSIX signals used to stop Chrome.
+///
+/// # Examples
+///
+/// ```text
+/// send_signal(&[4242], Signal::Term)?;
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Signal {
+    /// SIGTERM, lets Chrome flush its session.
+    Term,
+    /// SIGKILL, cannot be caught.
+    Kill,
+}
+
+impl Signal {
+    /// Signal name without the `SIG` prefix.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Term => "TERM",
+            Self::Kill => "KILL",
+        }
+    }
+
+    #[cfg(unix)]
+    fn raw(self) -> libc::c_int {
+        match self {
+            Self::Term => libc::SIGTERM,
+            Self::Kill => libc::SIGKILL,
+        }
+    }
+}
+
+/// List Chrome processes from the system procfs.
+///
+/// # Examples
+///
+/// ```text
+/// let processes = list_chrome_processes()?;
+/// ```
+pub fn list_chrome_processes() -> AppResult<Vec<ChromeProcess>> {
+    list_chrome_processes_in(Path::new(PROC_ROOT))
+}
+
+/// List Chrome processes below a procfs root, sorted by PID.
+///
+/// Processes that exit while being read, or whose entries cannot be read,
+/// are skipped.
+///
+/// # Examples
+///
+/// ```text
+/// let processes = list_chrome_processes_in(Path::new("/proc"))?;
+/// ```
+pub fn list_chrome_processes_in(proc_root: &Path) -> AppResult<Vec<ChromeProcess>> {
+    let mut processes = Vec::new();
+    for entry in fs::read_dir(proc_root)? {
+        let entry = entry?;
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<i32>().ok())
+        else {
+            continue;
+        };
+        if let Some(process) = read_process(&entry.path(), pid) {
+            processes.push(process);
+        }
+    }
+    processes.sort_by_key(|p| p.pid);
+    Ok(processes)
+}
+
+/// Read a single `/proc/<pid>` entry, returning `None` if it is not Chrome.
+fn read_process(dir: &Path, pid: i32) -> Option<ChromeProcess> {
+    let comm = fs::read_to_string(dir.join("comm")).ok()?;
+    let cmdline = parse_cmdline(&fs::read(dir.join("cmdline")).ok()?);
+    let exe = fs::read_link(dir.join("exe"))
+        .ok()
+        .or_else(|| cmdline.first().map(PathBuf::from))?;
+
+    let exe_name = exe.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let exe_name = exe_name.trim_end_matches(" (deleted)");
+    if !CHROME_NAMES.contains(&comm.trim()) && !CHROME_NAMES.contains(&exe_name) {
+        return None;
+    }
+
+    let status = fs::read_to_string(dir.join("status")).ok()?;
+    let uid = parse_status_field(&status, "Uid:")?;
+    let ppid = parse_status_field(&status, "PPid:").unwrap_or(0) as i32;
+    let kind = if cmdline.iter().any(|arg| arg.starts_with("--type=")) {
+        ProcessKind::Helper
+    } else {
+        ProcessKind::Browser
+    };
+
+    // Chrome resolves a relative --user-data-dir against its working directory
+    let user_data_dir =
+        user_data_dir_arg(&cmdline).map(|arg| match fs::read_link(dir.join("cwd")) {
+            Ok(cwd) if arg.is_relative() => cwd.join(arg),
+            _ => arg,
+        });
+
+    Some(ChromeProcess {
+        pid,
+        ppid,
+        uid,
+        channel: ChromeChannel::from_exe(&exe),
+        exe,
+        user_data_dir,
+        kind,
+        cmdline,
+    })
+}
+
+/// Whether `pid` is a live Chrome process.
+///
+/// Falls back to a plain liveness check where procfs is not available.
+///
+/// # Examples
+///
+/// ```text
+/// let alive = is_chrome_pid(4242);
+/// ```
+pub fn is_chrome_pid(pid: i32) -> bool {
+    let proc_root = Path::new(PROC_ROOT);
+    if proc_root.is_dir() {
+        read_process(&proc_root.join(pid.to_string()), pid).is_some()
+    } else {
+        pid_alive(pid)
+    }
+}
+
+/// Whether a process with `pid` exists.
+///
+/// # Examples
+///
+/// ```text
+/// let alive = pid_alive(4242);
+/// ```
+#[cfg(unix)]
+pub fn pid_alive(pid: i32) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+    // SAFETY: kill(2) with signal 0 only performs permission and existence checks.
+    let rc = unsafe { libc::kill(pid, 0) };
+    rc == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Whether a process with `pid` exists.
+///
+/// Not supported on this platform; always `false`.
+#[cfg(not(unix))]
+pub fn pid_alive(_pid: i32) -> bool {
+    false
+}
+
+/// Split a NUL-separated `cmdline` buffer into arguments.
+fn parse_cmdline(raw: &[u8]) -> Vec<String> {
+    raw.split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// Extract the first number of a `status` field such as `Uid:` or `PPid:`.
+fn parse_status_field(status: &str, field: &str) -> Option<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|uid| uid.parse().ok())
+}
+
+/// Find `--user-data-dir=<path>` or `--user-data-dir <path>` in a command line.
+fn user_data_dir_arg(cmdline: &[String]) -> Option<PathBuf> {
+    let mut args = cmdline.iter();
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg.strip_prefix("--user-data-dir=") {
+            return Some(PathBuf::from(value));
+        }
+        if arg == "--user-data-dir" {
+            return args.next().map(PathBuf::from);
+        }
+    }
+    None
+}
+
+/// Send a signal to each PID.
+///
+/// PIDs that no longer exist are ignored.
+///
+/// # Examples
+///
+/// ```text
+/// send_signal(&[4242, 4243], Signal::Term)?;
+/// ```
+#[cfg(unix)]
+pub fn send_signal(pids: &[i32], signal: Signal) -> AppResult<()> {
+    for &pid in pids {
+        // SAFETY: kill(2) has no memory-safety preconditions.
+        let rc = unsafe { libc::kill(pid, signal.raw()) };
+        if rc != 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ESRCH) {
+                continue;
+            }
+            return Err(AppError::SignalFailed {
+                pid,
+                signal: signal.name(),
+                source: err,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Send a signal to each PID.
+///
+/// Signals are not available on this platform.
+#[cfg(not(unix))]
+pub fn send_signal(pids: &[i32], signal: Signal) -> AppResult<()> {
+    match pids.first() {
+        None => Ok(()),
+        Some(&pid) => Err(AppError::SignalFailed {
+            pid,
+            signal: signal.name(),
+            source: io::Error::from(io::ErrorKind::Unsupported),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fake_process(root: &Path, pid: i32, comm: &str, exe: &str, args: &[&str], uid: u32) {
+        fake_child(root, pid, 1, comm, exe, args, uid);
+    }
+
+    fn fake_child(
+        root: &Path,
+        pid: i32,
+        ppid: i32,
+        comm: &str,
+        exe: &str,
+        args: &[&str],
+        uid: u32,
+    ) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("comm"), format!("{comm}\n")).unwrap();
+        let mut cmdline = Vec::new();
+        for arg in std::iter::once(&exe).chain(args) {
+            cmdline.extend_from_slice(arg.as_bytes());
+            cmdline.push(0);
+        }
+        fs::write(dir.join("cmdline"), cmdline).unwrap();
+        fs::write(
+            dir.join("status"),
+            format!("Name:\t{comm}\nPPid:\t{ppid}\nUid:\t{uid}\t{uid}\t{uid}\t{uid}\n"),
+        )
+        .unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(exe, dir.join("exe")).unwrap();
+    }
+
+    #[test]
+    fn discovers_browser_and_helpers() {
+        let root = TempDir::new().unwrap();
+        fake_process(
+            root.path(),
+            300,
+            "chrome",
+            "/opt/google/chrome/chrome",
+            &[],
+            1000,
+        );
+        fake_process(
+            root.path(),
+            301,
+            "chrome",
+            "/opt/google/chrome/chrome",
+            &["--type=renderer"],
+            1000,
+        );
+        fake_process(root.path(), 302, "bash", "/usr/bin/bash", &[], 1000);
+        fs::create_dir_all(root.path().join("self")).unwrap();
+
+        let processes = list_chrome_processes_in(root.path()).unwrap();
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[0].pid, 300);
+        assert!(processes[0].is_browser());
+        assert_eq!(processes[0].uid, 1000);
+        assert_eq!(processes[0].channel, ChromeChannel::Stable);
+        assert_eq!(processes[1].kind, ProcessKind::Helper);
+    }
+
+    #[test]
+    fn reads_user_data_dir_and_channel() {
+        let root = TempDir::new().unwrap();
+        fake_process(
+            root.path(),
+            10,
+            "chromium-browse",
+            "/usr/lib/chromium/chromium",
+            &["--user-data-dir=/tmp/profile"],
+            0,
+        );
+        fake_process(
+            root.path(),
+            11,
+            "chrome",
+            "/opt/google/chrome-beta/chrome",
+            &["--user-data-dir", "/tmp/beta"],
+            1001,
+        );
+
+        let processes = list_chrome_processes_in(root.path()).unwrap();
+        assert_eq!(processes[0].channel, ChromeChannel::Chromium);
+        assert_eq!(
+            processes[0].user_data_dir,
+            Some(PathBuf::from("/tmp/profile"))
+        );
+        assert_eq!(processes[1].channel, ChromeChannel::Beta);
+        assert_eq!(processes[1].user_data_dir, Some(PathBuf::from("/tmp/beta")));
+        assert_eq!(processes[1].uid, 1001);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolves_relative_user_data_dir_against_cwd() {
+        let root = TempDir::new().unwrap();
+        let args = ["--user-data-dir=profiles/work"];
+        fake_process(
+            root.path(),
+            20,
+            "chrome",
+            "/opt/google/chrome/chrome",
+            &args,
+            1000,
+        );
+        std::os::unix::fs::symlink("/home/me", root.path().join("20/cwd")).unwrap();
+        fake_process(
+            root.path(),
+            21,
+            "chrome",
+            "/opt/google/chrome/chrome",
+            &args,
+            1000,
+        );
+
+        let processes = list_chrome_processes_in(root.path()).unwrap();
+        assert_eq!(
+            processes[0].user_data_dir,
+            Some(PathBuf::from("/home/me/profiles/work"))
+        );
+        // Without a readable cwd the argument is kept as given
+        assert_eq!(
+            processes[1].user_data_dir,
+            Some(PathBuf::from("profiles/work"))
+        );
+    }
+
+    #[test]
+    fn parses_status_fields() {
+        let status = "Name:\tchrome\nPPid:\t42\nUid:\t1000\t1000\t1000\t1000\n";
+        assert_eq!(parse_status_field(status, "Uid:"), Some(1000));
+        assert_eq!(parse_status_field(status, "PPid:"), Some(42));
+        assert_eq!(parse_status_field("Name:\tchrome\n", "Uid:"), None);
+    }
+
+    #[test]
+    fn scopes_to_target_user_and_dir() {
+        let root = TempDir::new().unwrap();
+        let exe = "/opt/google/chrome/chrome";
+        // Target browser on the default dir, plus its zygote and renderer
+        fake_process(root.path(), 100, "chrome", exe, &[], 1000);
+        fake_child(
+            root.path(),
+            101,
+            100,
+            "chrome",
+            exe,
+            &["--type=zygote"],
+            1000,
+        );
+        fake_child(
+            root.path(),
+            102,
+            101,
+            "chrome",
+            exe,
+            &["--type=renderer"],
+            1000,
+        );
+        // Same user, separate test profile
+        fake_process(
+            root.path(),
+            200,
+            "chrome",
+            exe,
+            &["--user-data-dir=/tmp/test"],
+            1000,
+        );
+        fake_child(
+            root.path(),
+            201,
+            200,
+            "chrome",
+            exe,
+            &["--type=zygote"],
+            1000,
+        );
+        // Another user on the same default dir name
+        fake_process(root.path(), 300, "chrome", exe, &[], 1001);
+
+        let scope = ProcessScope {
+            uid: 1000,
+            user_data_dir: PathBuf::from("/home/me/.config/google-chrome"),
+            config_home: PathBuf::from("/home/me/.config"),
+        };
+        let processes = list_chrome_processes_in(root.path()).unwrap();
+        let scoped = partition_processes(processes, &scope);
+
+        assert_eq!(scoped.targeted_pids(), vec![100, 101, 102]);
+        let ignored: Vec<i32> = scoped.ignored.iter().map(|p| p.pid).collect();
+        assert_eq!(ignored, vec![200, 201, 300]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pid_alive_checks_existence() {
+        assert!(pid_alive(std::process::id() as i32));
+        assert!(!pid_alive(i32::MAX - 1));
+        assert!(!pid_alive(0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signal_to_missing_pid_is_ignored() {
+        // PID near the maximum is practically never allocated
+        assert!(send_signal(&[i32::MAX - 1], Signal::Term).is_ok());
+    }
+}
@@ -0,0 +1,256 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+
+/// Directory name of Chrome's first profile.
+pub const DEFAULT_PROFILE_DIR: &str = "Default";
+
+/// Per-profile preferences file name.
+pub const PREFERENCES_FILE: &str = "Preferences";
+
+/// Profile directories that were open in the last session, from Local State.
+///
+/// Uses `profile.last_active_profiles`, plus `profile.last_used`, falling
+/// back to `Default` when neither is present.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::profile::open_profile_dirs;
+///
+/// let local_state = r#"{"profile": {"last_active_profiles": ["Profile 1"], "last_used": "Default"}}"#;
+/// assert_eq!(open_profile_dirs(local_state).unwrap(), vec!["Profile 1", "Default"]);
+/// ```
+pub fn open_profile_dirs(local_state: &str) -> AppResult<Vec<String>> {
+    let json: Value = serde_json::from_str(local_state)
+        .map_err(|e| AppError::InvalidJson(format!("Local State parsing failed: {e}")))?;
+    let profile = &json["profile"];
+
+    let mut dirs: Vec<String> = profile["last_active_profiles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|dir| dir.as_str().map(str::to_string))
+        .collect();
+    if let Some(last_used) = profile["last_used"].as_str()
+        && !dirs.iter().any(|dir| dir == last_used)
+    {
+        dirs.push(last_used.to_string());
+    }
+    if dirs.is_empty() {
+        dirs.push(DEFAULT_PROFILE_DIR.to_string());
+    }
+    Ok(dirs)
+}
+
+/// A profile listed in Local State's `profile.info_cache`.
+///
+/// # Examples
+///
+/// ```text
+/// ProfileInfo { dir: "Profile 1".into(), name: "Work".into(), managed: true, .. }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProfileInfo {
+    /// Directory name inside the user-data dir, e.g. `Default` or `Profile 1`.
+    pub dir: String,
+    /// Display name.
+    pub name: String,
+    /// Avatar resource, e.g. `chrome://theme/IDR_PROFILE_AVATAR_26`.
+    pub avatar_icon: Option<String>,
+    /// Signed-in account email.
+    pub user_name: Option<String>,
+    /// Google Workspace domain of the account.
+    pub hosted_domain: Option<String>,
+    /// Whether the profile is enterprise-managed.
+    pub managed: bool,
+    /// Whether this is `profile.last_used`.
+    pub last_used: bool,
+}
+
+/// Raw `info_cache` entry; Chrome adds fields freely, so unknown ones are ignored.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct InfoCacheEntry {
+    name: String,
+    avatar_icon: Option<String>,
+    user_name: Option<String>,
+    hosted_domain: Option<String>,
+    enterprise_label: Option<String>,
+    user_accepted_account_management: Option<bool>,
+}
+
+/// Placeholder Chrome stores for consumer accounts.
+const NO_HOSTED_DOMAIN: &str = "NO_HOSTED_DOMAIN";
+
+impl From<(String, InfoCacheEntry)> for ProfileInfo {
+    fn from((dir, entry): (String, InfoCacheEntry)) -> Self {
+        let hosted_domain = entry
+            .hosted_domain
+            .filter(|domain| !domain.is_empty() && domain != NO_HOSTED_DOMAIN);
+        let managed = entry.user_accepted_account_management == Some(true)
+            || hosted_domain.is_some()
+            || entry
+                .enterprise_label
+                .is_some_and(|label| !label.is_empty());
+        Self {
+            dir,
+            name: entry.name,
+            avatar_icon: entry.avatar_icon,
+            user_name: entry.user_name.filter(|user| !user.is_empty()),
+            hosted_domain,
+            managed,
+            last_used: false,
+        }
+    }
+}
+
+/// List profiles from Local State's `profile.info_cache`.
+///
+/// Profiles are ordered `Default` first, then by profile number.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::profile::list_profiles;
+///
+/// let local_state = r#"{"profile": {"last_used": "Profile 1", "info_cache": {
+///     "Default": {"name": "Personal"},
+///     "Profile 1": {"name": "Work", "hosted_domain": "corp.example"}
+/// }}}"#;
+/// let profiles = list_profiles(local_state).unwrap();
+/// assert_eq!(profiles[1].name, "Work");
+/// assert!(profiles[1].managed && profiles[1].last_used);
+/// ```
+pub fn list_profiles(local_state: &str) -> AppResult<Vec<ProfileInfo>> {
+    let json: Value = serde_json::from_str(local_state)
+        .map_err(|e| AppError::InvalidJson(format!("Local State parsing failed: {e}")))?;
+    let last_used = json["profile"]["last_used"].as_str();
+
+    let mut profiles = Vec::new();
+    if let Some(cache) = json["profile"]["info_cache"].as_object() {
+        for (dir, entry) in cache {
+            let entry: InfoCacheEntry = serde_json::from_value(entry.clone()).map_err(|e| {
+                AppError::InvalidJson(format!("Profile {dir} in info_cache is invalid: {e}"))
+            })?;
+            let mut profile = ProfileInfo::from((dir.clone(), entry));
+            profile.last_used = last_used == Some(dir.as_str());
+            profiles.push(profile);
+        }
+    }
+    profiles.sort_by_key(|p| profile_sort_key(&p.dir));
+    Ok(profiles)
+}
+
+/// Sort key placing `Default` first, then `Profile N` numerically.
+fn profile_sort_key(dir: &str) -> (u8, u64, String) {
+    if dir == DEFAULT_PROFILE_DIR {
+        return (0, 0, String::new());
+    }
+    match dir.strip_prefix("Profile ").and_then(|n| n.parse().ok()) {
+        Some(n) => (1, n, String::new()),
+        None => (2, 0, dir.to_string()),
+    }
+}
+
+/// Select a profile by directory name, or by display name ignoring case.
+///
+/// # Examples
+///
+/// ```text
+/// let work = select_profile(&profiles, "Work")?;
+/// ```
+pub fn select_profile<'a>(
+    profiles: &'a [ProfileInfo],
+    selector: &str,
+) -> AppResult<&'a ProfileInfo> {
+    if let Some(profile) = profiles.iter().find(|p| p.dir == selector) {
+        return Ok(profile);
+    }
+    let by_name: Vec<&ProfileInfo> = profiles
+        .iter()
+        .filter(|p| p.name.eq_ignore_ascii_case(selector))
+        .collect();
+    match by_name.as_slice() {
+        [profile] => Ok(profile),
+        [] => Err(AppError::ProfileNotFound(selector.to_string())),
+        many => Err(AppError::AmbiguousProfile {
+            selector: selector.to_string(),
+            dirs: many
+                .iter()
+                .map(|p| p.dir.clone())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_default_profile() {
+        assert_eq!(open_profile_dirs("{}").unwrap(), vec![DEFAULT_PROFILE_DIR]);
+    }
+
+    #[test]
+    fn deduplicates_last_used() {
+        let local_state = r#"{"profile": {"last_active_profiles": ["Default", "Profile 2"], "last_used": "Default"}}"#;
+        assert_eq!(
+            open_profile_dirs(local_state).unwrap(),
+            vec!["Default", "Profile 2"]
+        );
+    }
+
+    const LOCAL_STATE: &str = r#"{"profile": {"last_used": "Default", "info_cache": {
+        "Profile 10": {"name": "Ten", "hosted_domain": "NO_HOSTED_DOMAIN"},
+        "Profile 2": {"name": "Work", "user_name": "me@corp.example", "user_accepted_account_management": true},
+        "Default": {"name": "Personal", "avatar_icon": "chrome://theme/IDR_PROFILE_AVATAR_26", "user_name": ""},
+        "Guest": {"name": "work"}
+    }}}"#;
+
+    #[test]
+    fn lists_profiles_in_order() {
+        let profiles = list_profiles(LOCAL_STATE).unwrap();
+        let dirs: Vec<&str> = profiles.iter().map(|p| p.dir.as_str()).collect();
+        assert_eq!(dirs, vec!["Default", "Profile 2", "Profile 10", "Guest"]);
+
+        assert!(profiles[0].last_used);
+        assert_eq!(profiles[0].user_name, None);
+        assert!(!profiles[0].managed);
+        assert!(profiles[1].managed);
+        assert_eq!(profiles[1].user_name.as_deref(), Some("me@corp.example"));
+        assert!(!profiles[2].managed);
+        assert_eq!(profiles[2].hosted_domain, None);
+    }
+
+    #[test]
+    fn lists_nothing_without_info_cache() {
+        assert!(list_profiles("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn selects_by_dir_or_name() {
+        let profiles = list_profiles(LOCAL_STATE).unwrap();
+        assert_eq!(select_profile(&profiles, "Profile 10").unwrap().name, "Ten");
+        assert_eq!(
+            select_profile(&profiles, "personal").unwrap().dir,
+            "Default"
+        );
+        assert!(matches!(
+            select_profile(&profiles, "Work"),
+            Err(AppError::AmbiguousProfile { .. })
+        ));
+        assert!(matches!(
+            select_profile(&profiles, "Nobody"),
+            Err(AppError::ProfileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_local_state() {
+        assert!(open_profile_dirs("{broken").is_err());
+    }
+}
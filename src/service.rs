@@ -0,0 +1,189 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::config_home;
+use crate::error::AppResult;
+use crate::files::GENERATED_MARKER;
+
+/// Oneshot service running `watch --once`.
+pub const SERVICE_UNIT: &str = "gemini-unlock.service";
+
+/// Path unit starting the service when Local State changes.
+pub const PATH_UNIT: &str = "gemini-unlock.path";
+
+/// Timer unit starting the service periodically.
+pub const TIMER_UNIT: &str = "gemini-unlock.timer";
+
+/// Every unit file `install-service` may write.
+pub const UNIT_FILES: &[&str] = &[SERVICE_UNIT, PATH_UNIT, TIMER_UNIT];
+
+/// What starts the service.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use gemini_unlock::service::{ServiceTrigger, TIMER_UNIT};
+///
+/// assert_eq!(ServiceTrigger::Timer(Duration::from_secs(900)).unit(), TIMER_UNIT);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ServiceTrigger {
+    /// Every change of Local State.
+    Path,
+    /// A timer firing at this interval.
+    Timer(Duration),
+}
+
+impl ServiceTrigger {
+    /// Unit that is enabled for this trigger.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Path => PATH_UNIT,
+            Self::Timer(_) => TIMER_UNIT,
+        }
+    }
+}
+
+/// Directory of the current user's systemd units: `$XDG_CONFIG_HOME/systemd/user`.
+///
+/// # Examples
+///
+/// ```text
+/// let dir = default_unit_dir()?; // ~/.config/systemd/user
+/// ```
+pub fn default_unit_dir() -> AppResult<PathBuf> {
+    Ok(config_home()?.join("systemd").join("user"))
+}
+
+/// Service unit running `command` once, without a terminal.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::service::service_unit;
+///
+/// let unit = service_unit(&["/usr/bin/gemini-unlock".into(), "watch".into(), "--once".into()]);
+/// assert!(unit.contains("\nType=oneshot\n"));
+/// assert!(unit.contains("\nExecStart=/usr/bin/gemini-unlock watch --once\n"));
+/// ```
+pub fn service_unit(command: &[String]) -> String {
+    let exec: Vec<String> = command.iter().map(|arg| exec_quote(arg)).collect();
+    format!(
+        "{}[Unit]\nDescription=Re-apply the Chrome Gemini patch when Chrome reverts it\n\n\
+         [Service]\nType=oneshot\nExecStart={}\n",
+        header(),
+        exec.join(" ")
+    )
+}
+
+/// Path or timer unit starting [`SERVICE_UNIT`].
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::service::{trigger_unit, ServiceTrigger};
+///
+/// let unit = trigger_unit(ServiceTrigger::Path, Path::new("/home/me/.config/google-chrome/Local State"));
+/// assert!(unit.contains("\nPathChanged=/home/me/.config/google-chrome/Local State\n"));
+/// ```
+pub fn trigger_unit(trigger: ServiceTrigger, chrome_state: &Path) -> String {
+    match trigger {
+        ServiceTrigger::Path => format!(
+            "{}[Unit]\nDescription=Watch Chrome's Local State for reverted Gemini settings\n\n\
+             [Path]\nPathChanged={}\nUnit={SERVICE_UNIT}\n\n\
+             [Install]\nWantedBy=default.target\n",
+            header(),
+            chrome_state.to_string_lossy().replace('%', "%%")
+        ),
+        ServiceTrigger::Timer(interval) => format!(
+            "{}[Unit]\nDescription=Re-apply the Chrome Gemini patch periodically\n\n\
+             [Timer]\nOnStartupSec=1min\nOnUnitActiveSec={}s\nUnit={SERVICE_UNIT}\n\n\
+             [Install]\nWantedBy=timers.target\n",
+            header(),
+            interval.as_secs().max(1)
+        ),
+    }
+}
+
+/// File names and contents of the service and its trigger unit.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::service::{unit_files, ServiceTrigger, PATH_UNIT, SERVICE_UNIT};
+///
+/// let files = unit_files(&["gemini-unlock".into()], ServiceTrigger::Path, Path::new("/tmp/Local State"));
+/// let names: Vec<&str> = files.iter().map(|(name, _)| *name).collect();
+/// assert_eq!(names, [SERVICE_UNIT, PATH_UNIT]);
+/// ```
+pub fn unit_files(
+    command: &[String],
+    trigger: ServiceTrigger,
+    chrome_state: &Path,
+) -> Vec<(&'static str, String)> {
+    vec![
+        (SERVICE_UNIT, service_unit(command)),
+        (trigger.unit(), trigger_unit(trigger, chrome_state)),
+    ]
+}
+
+fn header() -> String {
+    format!("{GENERATED_MARKER}; remove with `gemini-unlock uninstall-service`\n")
+}
+
+/// Quote an `ExecStart=` argument; `%` and `$` are escaped so systemd does
+/// not expand them as specifiers or variables.
+fn exec_quote(value: &str) -> String {
+    let escaped = value.replace('%', "%%").replace('$', "$$");
+    if !escaped.is_empty()
+        && escaped
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+=:,@%$".contains(c))
+    {
+        return escaped;
+    }
+    let mut quoted = String::from("\"");
+    for c in escaped.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::files::is_generated;
+
+    #[test]
+    fn quotes_exec_arguments() {
+        let unit = service_unit(&[
+            "/opt/gemini unlock/gemini-unlock".into(),
+            "--config".into(),
+            "/home/me/100%/say \"hi\"".into(),
+            "--profile".into(),
+            "$HOME".into(),
+        ]);
+        assert!(unit.contains(
+            "\nExecStart=\"/opt/gemini unlock/gemini-unlock\" --config \"/home/me/100%%/say \\\"hi\\\"\" --profile $$HOME\n"
+        ));
+        assert!(is_generated(&unit));
+    }
+
+    #[test]
+    fn timer_uses_interval() {
+        let unit = trigger_unit(
+            ServiceTrigger::Timer(Duration::from_secs(900)),
+            Path::new("/tmp/Local State"),
+        );
+        assert!(unit.contains("\nOnUnitActiveSec=900s\nUnit=gemini-unlock.service\n"));
+        assert!(unit.contains("\nWantedBy=timers.target\n"));
+        assert!(is_generated(&unit));
+    }
+}
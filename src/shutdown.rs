@@ -0,0 +1,333 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Action taken by one shutdown stage.
+///
+/// # Examples
+///
+/// ```text
+/// StageAction::Term
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StageAction {
+    /// Ask the browser to quit: SIGTERM to the browser process only on Linux,
+    /// `quit app` on macOS, `taskkill` without `/F` on Windows.
+    Close,
+    /// SIGTERM to every targeted process.
+    Term,
+    /// SIGKILL (`taskkill /F` on Windows).
+    Kill,
+}
+
+impl StageAction {
+    /// Whether this stage forcibly ends Chrome without letting it save state.
+    pub fn is_forced(self) -> bool {
+        self == Self::Kill
+    }
+
+    /// Stage name used in specs, config files and logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Close => "close",
+            Self::Term => "term",
+            Self::Kill => "kill",
+        }
+    }
+}
+
+impl fmt::Display for StageAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for StageAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "close" => Ok(Self::Close),
+            "term" => Ok(Self::Term),
+            "kill" => Ok(Self::Kill),
+            other => Err(format!("unknown shutdown stage: {other}")),
+        }
+    }
+}
+
+/// One step of a shutdown policy.
+///
+/// # Examples
+///
+/// ```text
+/// ShutdownStage { action: StageAction::Term, timeout_ms: 10_000 }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ShutdownStage {
+    /// What to do.
+    pub action: StageAction,
+    /// How long to wait for Chrome to exit before the next stage.
+    pub timeout_ms: u64,
+}
+
+impl ShutdownStage {
+    /// Stage timeout as a [`Duration`].
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// Ordered escalation used to stop Chrome.
+///
+/// Parsed from `--shutdown` as `stage:timeout` pairs, where timeouts take
+/// an `ms`, `s` or `m` suffix (seconds when omitted).
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::shutdown::{ShutdownPolicy, StageAction};
+///
+/// let policy: ShutdownPolicy = "close:5s,term:10,kill:500ms".parse().unwrap();
+/// assert_eq!(policy.stages.len(), 3);
+/// assert_eq!(policy.stages[2].action, StageAction::Kill);
+/// assert_eq!(policy.stages[2].timeout_ms, 500);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShutdownPolicy {
+    /// Stages in escalation order.
+    pub stages: Vec<ShutdownStage>,
+    /// How often to check whether Chrome has exited.
+    pub poll_interval_ms: u64,
+}
+
+impl ShutdownPolicy {
+    /// Poll interval as a [`Duration`].
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+
+    /// Check what deserialization alone does not: at least one stage, a
+    /// non-zero poll interval, and timeouts of at most [`MAX_TIMEOUT_MS`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gemini_unlock::shutdown::ShutdownPolicy;
+    ///
+    /// assert!(ShutdownPolicy::default().validate().is_ok());
+    /// let busy = ShutdownPolicy { poll_interval_ms: 0, ..Default::default() };
+    /// assert!(busy.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), String> {
+        if self.stages.is_empty() {
+            return Err("shutdown policy needs at least one stage".to_string());
+        }
+        if self.poll_interval_ms == 0 || self.poll_interval_ms > MAX_TIMEOUT_MS {
+            return Err(format!(
+                "poll interval must be between 1 and {MAX_TIMEOUT_MS} ms, got {}",
+                self.poll_interval_ms
+            ));
+        }
+        match self.stages.iter().find(|stage| stage.timeout_ms > MAX_TIMEOUT_MS) {
+            Some(stage) => Err(format!(
+                "{} timeout exceeds {MAX_TIMEOUT_MS} ms: {}",
+                stage.action, stage.timeout_ms
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for ShutdownPolicy {
+    fn default() -> Self {
+        Self {
+            stages: vec![
+                ShutdownStage {
+                    action: StageAction::Close,
+                    timeout_ms: 10_000,
+                },
+                ShutdownStage {
+                    action: StageAction::Term,
+                    timeout_ms: 10_000,
+                },
+                ShutdownStage {
+                    action: StageAction::Kill,
+                    timeout_ms: 5_000,
+                },
+            ],
+            poll_interval_ms: 300,
+        }
+    }
+}
+
+impl FromStr for ShutdownPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stages = s
+            .split(',')
+            .filter(|part| !part.trim().is_empty())
+            .map(|part| {
+                let (action, timeout) = part
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected stage:timeout, got {part}"))?;
+                Ok(ShutdownStage {
+                    action: action.parse()?,
+                    timeout_ms: parse_timeout_ms(timeout)?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let policy = Self {
+            stages,
+            ..Self::default()
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+}
+
+/// Longest accepted timeout, one day.
+pub const MAX_TIMEOUT_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Parse `500ms`, `5s`, `10m` or a bare number of seconds into milliseconds,
+/// up to [`MAX_TIMEOUT_MS`].
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::shutdown::parse_timeout_ms;
+///
+/// assert_eq!(parse_timeout_ms("2m"), Ok(120_000));
+/// assert_eq!(parse_timeout_ms("250ms"), Ok(250));
+/// ```
+pub fn parse_timeout_ms(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let invalid = || format!("invalid timeout: {value}");
+    let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 1)
+    } else if let Some(minutes) = value.strip_suffix('m') {
+        (minutes, 60_000)
+    } else {
+        (value.strip_suffix('s').unwrap_or(value), 1000)
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(scale))
+        .filter(|ms| *ms <= MAX_TIMEOUT_MS)
+        .ok_or_else(invalid)
+}
+
+/// What one shutdown stage did.
+///
+/// # Examples
+///
+/// ```text
+/// StageOutcome { action: StageAction::Term, pids: vec![4242], stopped: true, .. }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StageOutcome {
+    /// Stage action.
+    pub action: StageAction,
+    /// PIDs the stage targeted.
+    pub pids: Vec<i32>,
+    /// Time spent waiting for Chrome to exit.
+    pub elapsed: Duration,
+    /// Whether Chrome had exited by the end of the stage.
+    pub stopped: bool,
+}
+
+/// Command that starts Chrome again the way it was running.
+///
+/// # Examples
+///
+/// ```text
+/// LaunchCommand { program: "/opt/google/chrome/chrome".into(), args: vec!["--incognito".into()] }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LaunchCommand {
+    /// Executable to run.
+    pub program: PathBuf,
+    /// Arguments, without `argv[0]`.
+    pub args: Vec<String>,
+}
+
+/// Record of a `stop_chrome` run.
+///
+/// # Examples
+///
+/// ```text
+/// let report = stop_chrome(os, &user_data_dir, &policy)?;
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShutdownReport {
+    /// Stages that ran, in order.
+    pub stages: Vec<StageOutcome>,
+    /// How to start the stopped browser again, if it could be recorded.
+    pub relaunch: Option<LaunchCommand>,
+}
+
+impl ShutdownReport {
+    /// Whether a forced stage had to run.
+    pub fn forced(&self) -> bool {
+        self.stages.iter().any(|stage| stage.action.is_forced())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_policy_spec() {
+        let policy: ShutdownPolicy = "term:30s, kill:2".parse().unwrap();
+        assert_eq!(
+            policy.stages,
+            vec![
+                ShutdownStage {
+                    action: StageAction::Term,
+                    timeout_ms: 30_000
+                },
+                ShutdownStage {
+                    action: StageAction::Kill,
+                    timeout_ms: 2_000
+                },
+            ]
+        );
+        assert_eq!(policy.poll_interval_ms, 300);
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        assert!("".parse::<ShutdownPolicy>().is_err());
+        assert!("term".parse::<ShutdownPolicy>().is_err());
+        assert!("nuke:5s".parse::<ShutdownPolicy>().is_err());
+        assert!("term:soon".parse::<ShutdownPolicy>().is_err());
+    }
+
+    #[test]
+    fn deserializes_from_config() {
+        let policy: ShutdownPolicy =
+            serde_json::from_str(r#"{"stages":[{"action":"kill","timeout_ms":100}]}"#).unwrap();
+        assert_eq!(policy.stages[0].action, StageAction::Kill);
+        assert_eq!(policy.poll_interval_ms, 300);
+    }
+
+    #[test]
+    fn report_detects_forced_stop() {
+        let mut report = ShutdownReport::default();
+        assert!(!report.forced());
+        report.stages.push(StageOutcome {
+            action: StageAction::Kill,
+            pids: vec![1],
+            elapsed: Duration::ZERO,
+            stopped: true,
+        });
+        assert!(report.forced());
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::compat::ChromeVersion;
+use crate::error::{AppError, AppResult};
+
+/// Patch state file name, kept next to `Local State`.
+pub const STATE_FILE: &str = "gemini-unlock-state.json";
+
+/// What the last successful patch ran against, and with which options.
+///
+/// # Examples
+///
+/// ```text
+/// PatchState { chrome_version: Some("140.0.7339.80".into()), rule_set: 2, .. }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatchState {
+    /// Chrome version at the time, if known.
+    pub chrome_version: Option<String>,
+    /// Id of the rule set that was applied.
+    pub rule_set: u32,
+    /// Version of the tool that applied it.
+    pub tool_version: String,
+    /// Seconds since the Unix epoch.
+    pub applied_at: u64,
+    /// `--profile` selector, reused by `reapply`.
+    pub profile: Option<String>,
+    /// `--locale` tag, reused by `reapply`.
+    pub locale: Option<String>,
+    /// `--accept-languages`, reused by `reapply`.
+    pub accept_languages: bool,
+}
+
+impl PatchState {
+    /// Load the state, or `None` if nothing was patched yet.
+    pub fn load(path: &Path) -> AppResult<Option<Self>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        serde_json::from_str(&content).map(Some).map_err(|e| {
+            AppError::InvalidJson(format!("Patch state {} is invalid: {e}", path.display()))
+        })
+    }
+
+    /// Serialize the state for writing.
+    pub fn to_json(&self) -> AppResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::InvalidJson(format!("Patch state serialization failed: {e}")))
+    }
+
+    /// What changed between this state and the current Chrome version and rule set.
+    ///
+    /// An unknown current version is not reported as an update.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gemini_unlock::state::{PatchState, StateChange};
+    ///
+    /// let state = PatchState { chrome_version: Some("139.0.7258.154".into()), rule_set: 1, ..Default::default() };
+    /// let changes = state.changes(Some(&"140.0.7339.80".parse().unwrap()), 2);
+    /// assert_eq!(changes.len(), 2);
+    /// assert!(matches!(changes[0], StateChange::ChromeUpdated { .. }));
+    /// ```
+    pub fn changes(&self, chrome_version: Option<&ChromeVersion>, rule_set: u32) -> Vec<StateChange> {
+        let mut changes = Vec::new();
+        if let Some(current) = chrome_version {
+            let recorded = self
+                .chrome_version
+                .as_deref()
+                .and_then(|version| version.parse::<ChromeVersion>().ok());
+            if recorded.as_ref() != Some(current) {
+                changes.push(StateChange::ChromeUpdated {
+                    from: self.chrome_version.clone(),
+                    to: current.to_string(),
+                });
+            }
+        }
+        if self.rule_set != rule_set {
+            changes.push(StateChange::RulesUpdated {
+                from: self.rule_set,
+                to: rule_set,
+            });
+        }
+        changes
+    }
+}
+
+/// A difference from the recorded patch state.
+///
+/// # Examples
+///
+/// ```text
+/// StateChange::RulesUpdated { from: 1, to: 2 }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StateChange {
+    /// Chrome was updated (or the version was unknown when patching).
+    ChromeUpdated { from: Option<String>, to: String },
+    /// A different rule set applies now.
+    RulesUpdated { from: u32, to: u32 },
+}
+
+impl fmt::Display for StateChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ChromeUpdated { from: Some(from), to } => {
+                write!(f, "Chrome was updated from {from} to {to}")
+            }
+            Self::ChromeUpdated { from: None, to } => {
+                write!(f, "Chrome {to} was not known when patching")
+            }
+            Self::RulesUpdated { from, to } => write!(f, "rule set changed from {from} to {to}"),
+        }
+    }
+}
+
+/// Current time in seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Coarse age of a timestamp, e.g. `3 days ago`.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::state::describe_age;
+///
+/// assert_eq!(describe_age(1_000, 1_030), "just now");
+/// assert_eq!(describe_age(0, 7_200), "2 hours ago");
+/// ```
+pub fn describe_age(then: u64, now: u64) -> String {
+    let secs = now.saturating_sub(then);
+    let (count, unit) = match secs {
+        0..60 => return "just now".to_string(),
+        60..3_600 => (secs / 60, "minute"),
+        3_600..86_400 => (secs / 3_600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_state_reports_nothing() {
+        let state = PatchState {
+            chrome_version: Some("140.0.7339.80".into()),
+            rule_set: 2,
+            ..Default::default()
+        };
+        let version = "140.0.7339.80".parse().unwrap();
+        assert!(state.changes(Some(&version), 2).is_empty());
+        assert!(state.changes(None, 2).is_empty());
+
+        let loaded: PatchState =
+            serde_json::from_str(&state.to_json().unwrap()).unwrap();
+        assert_eq!(loaded, state);
+    }
+
+    #[test]
+    fn reports_version_learned_after_patching() {
+        let state = PatchState {
+            rule_set: 2,
+            ..Default::default()
+        };
+        let version = "141.0.7390.54".parse().unwrap();
+        let changes = state.changes(Some(&version), 2);
+        assert_eq!(changes[0].to_string(), "Chrome 141.0.7390.54 was not known when patching");
+    }
+
+    #[test]
+    fn ages_are_coarse() {
+        assert_eq!(describe_age(0, 60), "1 minute ago");
+        assert_eq!(describe_age(0, 3 * 86_400 + 5), "3 days ago");
+        assert_eq!(describe_age(100, 50), "just now");
+    }
+}
@@ -0,0 +1,379 @@
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+use crate::files::staging_path;
+
+/// Outcome of one file in a [`Transaction`].
+///
+/// # Examples
+///
+/// ```text
+/// FileOutcome::Committed
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FileOutcome {
+    /// Written and kept.
+    Committed,
+    /// Written, then restored to its original content after a later failure.
+    RolledBack,
+    /// Never written because the transaction failed first.
+    Skipped,
+    /// The step that failed for this file.
+    Failed(String),
+}
+
+impl fmt::Display for FileOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Committed => f.write_str("committed"),
+            Self::RolledBack => f.write_str("rolled back"),
+            Self::Skipped => f.write_str("not written"),
+            Self::Failed(reason) => write!(f, "failed: {reason}"),
+        }
+    }
+}
+
+/// Per-file result of a transaction.
+///
+/// # Examples
+///
+/// ```text
+/// FileResult { label: "Local State".into(), outcome: FileOutcome::Committed, .. }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileResult {
+    /// Human-readable name, e.g. `Local State` or `Profile 1/Preferences`.
+    pub label: String,
+    /// Target path.
+    pub path: PathBuf,
+    /// What happened to it.
+    pub outcome: FileOutcome,
+}
+
+/// Combined report of a [`Transaction::commit`].
+///
+/// # Examples
+///
+/// ```text
+/// let report = transaction.commit();
+/// report.into_result()?;
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TransactionReport {
+    /// Every staged file, in staging order.
+    pub files: Vec<FileResult>,
+    /// Why the transaction failed, if it did.
+    pub error: Option<String>,
+}
+
+impl TransactionReport {
+    /// Whether every file was committed.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Convert a failed transaction into [`AppError::TransactionFailed`].
+    pub fn into_result(self) -> AppResult<Self> {
+        match &self.error {
+            None => Ok(self),
+            Some(reason) => Err(AppError::TransactionFailed(reason.clone())),
+        }
+    }
+}
+
+/// A file written to a staging path, waiting to be committed.
+#[derive(Debug)]
+struct StagedFile {
+    label: String,
+    target: PathBuf,
+    staged: PathBuf,
+    expected: String,
+    json: bool,
+}
+
+/// All-or-nothing update of several files.
+///
+/// Outputs are staged next to their targets, verified, then renamed into
+/// place one by one. If any commit fails, every file already committed is
+/// restored to its original content.
+///
+/// # Examples
+///
+/// ```text
+/// let mut tx = Transaction::new();
+/// tx.stage_json("Local State", &chrome_state, &content)?;
+/// tx.stage_json("Default/Preferences", &preferences, &prefs)?;
+/// tx.commit().into_result()?;
+/// ```
+#[derive(Debug, Default)]
+pub struct Transaction {
+    files: Vec<StagedFile>,
+}
+
+impl Transaction {
+    /// Create an empty transaction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Target paths of the staged files, in staging order.
+    pub fn targets(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|file| file.target.as_path())
+    }
+
+    /// Number of staged files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether nothing is staged.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Stage a file whose content must read back unchanged.
+    pub fn stage(&mut self, label: &str, target: &Path, content: &str) -> AppResult<()> {
+        self.stage_file(label, target, content, false)
+    }
+
+    /// Stage a JSON file; verification also checks that it parses.
+    pub fn stage_json(&mut self, label: &str, target: &Path, content: &str) -> AppResult<()> {
+        self.stage_file(label, target, content, true)
+    }
+
+    fn stage_file(
+        &mut self,
+        label: &str,
+        target: &Path,
+        content: &str,
+        json: bool,
+    ) -> AppResult<()> {
+        let staged = staging_path(target, "staged")?;
+        let mut file = fs::File::create(&staged)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(meta) = fs::metadata(target) {
+            fs::set_permissions(&staged, meta.permissions())?;
+        }
+        self.files.push(StagedFile {
+            label: label.to_string(),
+            target: target.to_path_buf(),
+            staged,
+            expected: content.to_string(),
+            json,
+        });
+        Ok(())
+    }
+
+    /// Verify every staged file, then commit them all or none.
+    pub fn commit(self) -> TransactionReport {
+        let mut report = TransactionReport {
+            files: self
+                .files
+                .iter()
+                .map(|file| FileResult {
+                    label: file.label.clone(),
+                    path: file.target.clone(),
+                    outcome: FileOutcome::Skipped,
+                })
+                .collect(),
+            error: None,
+        };
+
+        // 1. Verify all staged outputs before touching any target
+        for (index, file) in self.files.iter().enumerate() {
+            if let Err(reason) = verify(file) {
+                report.files[index].outcome = FileOutcome::Failed(reason.clone());
+                report.error = Some(format!("{}: {reason}", file.label));
+                return report;
+            }
+        }
+
+        // 2. Commit one by one, keeping a copy of each original for rollback
+        let mut committed: Vec<(usize, Option<PathBuf>)> = Vec::new();
+        for (index, file) in self.files.iter().enumerate() {
+            match commit_file(file) {
+                Ok(rollback) => {
+                    report.files[index].outcome = FileOutcome::Committed;
+                    committed.push((index, rollback));
+                }
+                Err(reason) => {
+                    log::error!("Commit of {} failed: {reason}", file.label);
+                    report.files[index].outcome = FileOutcome::Failed(reason.clone());
+                    report.error = Some(format!("{}: {reason}", file.label));
+                    break;
+                }
+            }
+        }
+
+        // 3. Roll back on failure, otherwise drop the rollback copies
+        if report.error.is_some() {
+            for (index, rollback) in committed.into_iter().rev() {
+                let file = &self.files[index];
+                report.files[index].outcome = match rollback_file(file, rollback.as_deref()) {
+                    Ok(()) => FileOutcome::RolledBack,
+                    Err(reason) => {
+                        log::error!("Rollback of {} failed: {reason}", file.label);
+                        FileOutcome::Failed(format!("rollback failed: {reason}"))
+                    }
+                };
+            }
+        } else {
+            for rollback in committed.into_iter().filter_map(|(_, rollback)| rollback) {
+                let _ = fs::remove_file(rollback);
+            }
+        }
+        report
+    }
+}
+
+impl Drop for Transaction {
+    /// Remove staged files that were not committed.
+    fn drop(&mut self) {
+        for file in &self.files {
+            let _ = fs::remove_file(&file.staged);
+        }
+    }
+}
+
+/// Check that a staged file reads back as written.
+fn verify(file: &StagedFile) -> Result<(), String> {
+    let written = fs::read_to_string(&file.staged).map_err(|e| format!("verify failed: {e}"))?;
+    if written != file.expected {
+        return Err("verify failed: staged content differs".to_string());
+    }
+    if file.json {
+        serde_json::from_str::<Value>(&written).map_err(|e| format!("verify failed: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Move a staged file into place, returning the rollback copy of the original.
+fn commit_file(file: &StagedFile) -> Result<Option<PathBuf>, String> {
+    let rollback = if file.target.exists() {
+        let rollback = staging_path(&file.target, "rollback").map_err(|e| e.to_string())?;
+        fs::copy(&file.target, &rollback).map_err(|e| format!("saving original failed: {e}"))?;
+        Some(rollback)
+    } else {
+        None
+    };
+    if let Err(e) = fs::rename(&file.staged, &file.target) {
+        if let Some(rollback) = &rollback {
+            let _ = fs::remove_file(rollback);
+        }
+        return Err(format!("rename failed: {e}"));
+    }
+    Ok(rollback)
+}
+
+/// Restore a committed file from its rollback copy, or remove it if it was new.
+fn rollback_file(file: &StagedFile, rollback: Option<&Path>) -> Result<(), String> {
+    match rollback {
+        Some(rollback) => fs::rename(rollback, &file.target).map_err(|e| e.to_string()),
+        None => fs::remove_file(&file.target).map_err(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn leftovers(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|entry| entry.unwrap().file_name().into_string().ok())
+            .filter(|name| name.starts_with('.'))
+            .collect()
+    }
+
+    #[test]
+    fn commits_all_files() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.json");
+        let b = dir.path().join("b.json");
+        fs::write(&a, "{}").unwrap();
+
+        let mut tx = Transaction::new();
+        tx.stage_json("a", &a, r#"{"a":1}"#).unwrap();
+        tx.stage_json("b", &b, r#"{"b":2}"#).unwrap();
+        let report = tx.commit();
+
+        assert!(report.is_success());
+        assert!(
+            report
+                .files
+                .iter()
+                .all(|f| f.outcome == FileOutcome::Committed)
+        );
+        assert_eq!(fs::read_to_string(&a).unwrap(), r#"{"a":1}"#);
+        assert_eq!(fs::read_to_string(&b).unwrap(), r#"{"b":2}"#);
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn rolls_back_committed_files_on_later_failure() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.json");
+        let b = dir.path().join("b.json");
+        let c = dir.path().join("c.json");
+        fs::write(&a, "{}").unwrap();
+        // A directory in place of the third target makes its commit fail
+        fs::create_dir(&c).unwrap();
+
+        let mut tx = Transaction::new();
+        tx.stage_json("a", &a, r#"{"a":1}"#).unwrap();
+        tx.stage_json("b", &b, r#"{"b":2}"#).unwrap();
+        tx.stage_json("c", &c, r#"{"c":3}"#).unwrap();
+        let report = tx.commit();
+
+        assert!(!report.is_success());
+        assert_eq!(report.files[0].outcome, FileOutcome::RolledBack);
+        assert_eq!(report.files[1].outcome, FileOutcome::RolledBack);
+        assert!(matches!(report.files[2].outcome, FileOutcome::Failed(_)));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "{}");
+        assert!(!b.exists());
+        assert!(leftovers(dir.path()).is_empty());
+        assert!(matches!(
+            report.into_result(),
+            Err(AppError::TransactionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_json_fails_verification_before_commit() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.json");
+        let b = dir.path().join("b.json");
+        fs::write(&a, "{}").unwrap();
+
+        let mut tx = Transaction::new();
+        tx.stage_json("a", &a, r#"{"a":1}"#).unwrap();
+        tx.stage_json("b", &b, "{broken").unwrap();
+        let report = tx.commit();
+
+        assert_eq!(report.files[0].outcome, FileOutcome::Skipped);
+        assert!(matches!(report.files[1].outcome, FileOutcome::Failed(_)));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "{}");
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn dropping_uncommitted_transaction_removes_staged_files() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.json");
+
+        let mut tx = Transaction::new();
+        tx.stage_json("a", &a, "{}").unwrap();
+        assert_eq!(leftovers(dir.path()).len(), 1);
+        drop(tx);
+
+        assert!(leftovers(dir.path()).is_empty());
+        assert!(!a.exists());
+    }
+}
@@ -0,0 +1,190 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde_json::Value;
+
+use crate::patcher::PatchRule;
+
+/// Modification time and size of a watched file.
+///
+/// # Examples
+///
+/// ```text
+/// let stamp = FileStamp::of(&chrome_state);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileStamp {
+    /// Last modification time.
+    pub modified: SystemTime,
+    /// Size in bytes.
+    pub len: u64,
+}
+
+impl FileStamp {
+    /// Stamp of `path`, or `None` if it cannot be read.
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: meta.modified().ok()?,
+            len: meta.len(),
+        })
+    }
+}
+
+/// Decides when a changed file has settled and may be checked.
+///
+/// Chrome writes Local State several times while exiting; checking only after
+/// the stamp has stayed the same for the settle time avoids racing it.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant, SystemTime};
+/// use gemini_unlock::watch::{FileStamp, SettleTracker};
+///
+/// let mut tracker = SettleTracker::new(Duration::from_secs(3));
+/// let stamp = Some(FileStamp { modified: SystemTime::UNIX_EPOCH, len: 10 });
+/// let start = Instant::now();
+/// assert!(!tracker.observe(stamp, start));
+/// assert!(tracker.observe(stamp, start + Duration::from_secs(3)));
+/// assert!(!tracker.observe(stamp, start + Duration::from_secs(6)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SettleTracker {
+    settle: Duration,
+    last: Option<FileStamp>,
+    pending_since: Option<Instant>,
+}
+
+impl SettleTracker {
+    /// Tracker that waits `settle` after the last change.
+    pub fn new(settle: Duration) -> Self {
+        Self {
+            settle,
+            last: None,
+            pending_since: None,
+        }
+    }
+
+    /// Feed the current stamp; returns `true` once per change, when it has settled.
+    ///
+    /// The first stamp counts as a change, so the file is checked at startup.
+    pub fn observe(&mut self, stamp: Option<FileStamp>, now: Instant) -> bool {
+        if stamp != self.last {
+            self.last = stamp;
+            self.pending_since = stamp.map(|_| now);
+            return false;
+        }
+        match self.pending_since {
+            Some(since) if now.duration_since(since) >= self.settle => {
+                self.pending_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Check the settled change again later, e.g. because Chrome is running.
+    pub fn postpone(&mut self, now: Instant) {
+        self.pending_since = Some(now);
+    }
+}
+
+/// A patched field Chrome changed back.
+///
+/// # Examples
+///
+/// ```text
+/// Drift { pointer: "/variations_country".into(), expected: json!("us"), actual: Some(json!("de")) }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drift {
+    /// JSON pointer of the field.
+    pub pointer: String,
+    /// Value the rule sets.
+    pub expected: Value,
+    /// Current value; `None` if Chrome removed the field.
+    pub actual: Option<Value>,
+}
+
+/// Fields that no longer have the value their rule sets.
+///
+/// A missing field only counts for rules that create it; the others are
+/// fields Chrome has not written yet.
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::patcher::local_state_rules;
+/// use gemini_unlock::watch::find_drift;
+/// use serde_json::json;
+///
+/// let local_state = json!({"is_glic_eligible": true, "variations_country": "de"});
+/// let drift = find_drift(&local_state, &local_state_rules());
+/// assert_eq!(drift.len(), 1);
+/// assert_eq!(drift[0].actual, Some(json!("de")));
+/// ```
+pub fn find_drift(json: &Value, rules: &[PatchRule]) -> Vec<Drift> {
+    rules
+        .iter()
+        .filter_map(|rule| match json.pointer(&rule.pointer) {
+            Some(value) if *value == rule.value => None,
+            None if !rule.create => None,
+            actual => Some(Drift {
+                pointer: rule.pointer.clone(),
+                expected: rule.value.clone(),
+                actual: actual.cloned(),
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::locale_rules;
+    use crate::patcher::local_state_rules;
+    use serde_json::json;
+
+    fn stamp(len: u64) -> Option<FileStamp> {
+        Some(FileStamp {
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(len),
+            len,
+        })
+    }
+
+    #[test]
+    fn waits_for_writes_to_stop() {
+        let settle = Duration::from_secs(3);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut tracker = SettleTracker::new(settle);
+
+        assert!(!tracker.observe(stamp(1), at(0)));
+        assert!(!tracker.observe(stamp(2), at(2)));
+        assert!(!tracker.observe(stamp(2), at(4)));
+        assert!(tracker.observe(stamp(2), at(5)));
+
+        tracker.postpone(at(5));
+        assert!(!tracker.observe(stamp(2), at(7)));
+        assert!(tracker.observe(stamp(2), at(8)));
+
+        assert!(!tracker.observe(None, at(9)));
+        assert!(!tracker.observe(None, at(20)));
+    }
+
+    #[test]
+    fn reports_reverted_and_removed_fields() {
+        let mut rules = local_state_rules();
+        rules.extend(locale_rules("en-US"));
+        let local_state = json!({
+            "is_glic_eligible": false,
+            "variations_country": "us",
+        });
+        let drift = find_drift(&local_state, &rules);
+        let pointers: Vec<&str> = drift.iter().map(|d| d.pointer.as_str()).collect();
+        assert_eq!(pointers, ["/is_glic_eligible", "/intl/app_locale"]);
+        assert_eq!(drift[1].actual, None);
+    }
+}
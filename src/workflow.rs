@@ -0,0 +1,721 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::compat::{ChromeVersion, RuleSet};
+use crate::config::PrefHashConfig;
+use crate::doctor::safe_seed_rules;
+use crate::error::{AppError, AppResult};
+use crate::files::backup_file;
+use crate::journal::{JOURNAL_FILE, Journal, unpatch};
+use crate::locale::{accept_languages_rules, locale_rules};
+use crate::patcher::{
+    PatchReport, PatchRule, RuleReport, apply_patches_with, apply_rules, preferences_rules,
+};
+use crate::prefhash::{SECURE_PREFERENCES_FILE, is_tracked, pref_path, update_macs};
+use crate::profile::{
+    DEFAULT_PROFILE_DIR, PREFERENCES_FILE, ProfileInfo, list_profiles, select_profile,
+};
+use crate::state::{PatchState, STATE_FILE, unix_now};
+use crate::transaction::{Transaction, TransactionReport};
+
+/// Transaction label of `Local State`, also its journal file name.
+pub const LOCAL_STATE_LABEL: &str = "Local State";
+
+/// What a patch run changes besides the fixed rule sets.
+///
+/// # Examples
+///
+/// ```text
+/// ApplyOptions { chrome_version, rule_set, profile: None, locale: Some("en-US"), .. }
+/// ```
+#[derive(Clone, Copy)]
+pub struct ApplyOptions<'a> {
+    /// Detected Chrome version, if known.
+    pub chrome_version: Option<ChromeVersion>,
+    /// Versioned Local State rule set.
+    pub rule_set: &'static RuleSet,
+    /// `--profile` selector.
+    pub profile: Option<&'a str>,
+    /// `--locale` tag.
+    pub locale: Option<&'a str>,
+    /// `--accept-languages`.
+    pub accept_languages: bool,
+    /// Point the safe seed's country at the region preset (`doctor --fix`).
+    pub sync_safe_seed: bool,
+    /// `--dry-run`.
+    pub dry_run: bool,
+    /// Seed and device ID that re-seal the MACs of edited protected preferences.
+    pub pref_hash: &'a PrefHashConfig,
+    /// Files already backed up in this run, before any edit.
+    pub backed_up: &'a [PathBuf],
+}
+
+/// What [`apply_workflow`] did with one profile.
+///
+/// # Examples
+///
+/// ```text
+/// ProfilePatch { dir: "Default".into(), rules: Some(report), sealed: 1 }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProfilePatch {
+    /// Profile directory.
+    pub dir: String,
+    /// Preferences rules applied; `None` if the profile has no Preferences file.
+    pub rules: Option<RuleReport>,
+    /// Protected preference MACs recomputed.
+    pub sealed: usize,
+}
+
+/// Everything [`apply_workflow`] changed, in the order it was changed.
+///
+/// # Examples
+///
+/// ```text
+/// let report = apply_workflow(&chrome_state, &user_data_dir, &options)?;
+/// report.transaction.map(TransactionReport::into_result).transpose()?;
+/// ```
+#[derive(Debug)]
+pub struct ApplyReport {
+    /// Fixed Local State fields.
+    pub patch: PatchReport,
+    /// Locale and safe-seed rules applied to Local State, if any were asked for.
+    pub local_state: Option<RuleReport>,
+    /// Every target profile.
+    pub profiles: Vec<ProfilePatch>,
+    /// How the files were written; `None` for a dry run.
+    pub transaction: Option<TransactionReport>,
+}
+
+/// Patch Local State and every target profile's Preferences as one transaction.
+///
+/// Originals of changed fields go to the unpatch journal and the run is
+/// recorded in the patch state. A failed write is rolled back and reported
+/// in [`ApplyReport::transaction`] rather than as an error.
+///
+/// # Examples
+///
+/// ```text
+/// let report = apply_workflow(&chrome_state, &user_data_dir, &options)?;
+/// ```
+pub fn apply_workflow(
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    options: &ApplyOptions,
+) -> AppResult<ApplyReport> {
+    let journal_path = user_data_dir.join(JOURNAL_FILE);
+    let mut journal = Journal::load(&journal_path)?;
+    let content = read_local_state(chrome_state)?;
+
+    let consistency = options
+        .rule_set
+        .consistency_value(options.chrome_version.as_ref());
+    let patch = apply_patches_with(&content, consistency.as_ref())?;
+    let mut content = patch.content.clone();
+    let mut recorded = false;
+    let mut rules = Vec::new();
+    if let Some(locale) = options.locale {
+        rules.extend(locale_rules(locale));
+    }
+    if options.sync_safe_seed {
+        rules.extend(safe_seed_rules());
+    }
+    let local_state = if rules.is_empty() {
+        None
+    } else {
+        let report = apply_rules(&content, &rules)?;
+        recorded = journal.record(LOCAL_STATE_LABEL, &report.changes);
+        content = report.content.clone();
+        Some(report)
+    };
+
+    let mut transaction = Transaction::new();
+    transaction.stage_json(LOCAL_STATE_LABEL, chrome_state, &content)?;
+    let mut rules = preferences_rules();
+    if let Some(locale) = options.locale
+        && options.accept_languages
+    {
+        rules.extend(accept_languages_rules(locale));
+    }
+    let mut profiles = Vec::new();
+    for dir in target_profile_dirs(&content, options.profile)? {
+        let Some(preferences) = preferences_path(user_data_dir, &dir) else {
+            continue;
+        };
+        if !preferences.exists() {
+            log::warn!("Preferences not found: {}", preferences.display());
+            profiles.push(ProfilePatch {
+                dir,
+                rules: None,
+                sealed: 0,
+            });
+            continue;
+        }
+        let report = apply_rules(&fs::read_to_string(&preferences)?, &rules)?;
+        let mut sealed = 0;
+        if !options.dry_run && report.changed() {
+            let label = format!("{dir}/{PREFERENCES_FILE}");
+            recorded |= journal.record(&label, &report.changes);
+            let pointers: Vec<&str> = report
+                .changes
+                .iter()
+                .map(|change| change.pointer.as_str())
+                .collect();
+            sealed = stage_preferences(
+                &mut transaction,
+                &label,
+                &preferences,
+                &report.content,
+                &pointers,
+                options.pref_hash,
+            )?;
+        }
+        profiles.push(ProfilePatch {
+            dir,
+            rules: Some(report),
+            sealed,
+        });
+    }
+
+    let mut report = ApplyReport {
+        patch,
+        local_state,
+        profiles,
+        transaction: None,
+    };
+    if options.dry_run {
+        return Ok(report);
+    }
+    if recorded {
+        transaction.stage_json("Unpatch journal", &journal_path, &journal.to_json()?)?;
+    }
+    let state = PatchState {
+        chrome_version: options.chrome_version.map(|version| version.to_string()),
+        rule_set: options.rule_set.id,
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        applied_at: unix_now(),
+        profile: options.profile.map(str::to_string),
+        locale: options.locale.map(str::to_string),
+        accept_languages: options.accept_languages,
+    };
+    transaction.stage_json(
+        "Patch state",
+        &user_data_dir.join(STATE_FILE),
+        &state.to_json()?,
+    )?;
+    report.transaction = Some(commit_workflow(transaction, options.backed_up)?);
+    Ok(report)
+}
+
+/// Apply `rules` to Local State alone, journaling the original values.
+///
+/// Used by `doctor --fix` for repairs that only touch Local State. Returns
+/// the rule report and how the files were written, or `None` if nothing
+/// changed.
+///
+/// # Examples
+///
+/// ```text
+/// let (report, transaction) = patch_local_state(&chrome_state, &user_data_dir, &safe_seed_rules())?;
+/// ```
+pub fn patch_local_state(
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    rules: &[PatchRule],
+) -> AppResult<(RuleReport, Option<TransactionReport>)> {
+    let report = apply_rules(&read_local_state(chrome_state)?, rules)?;
+    if !report.changed() {
+        return Ok((report, None));
+    }
+    let journal_path = user_data_dir.join(JOURNAL_FILE);
+    let mut journal = Journal::load(&journal_path)?;
+    let mut transaction = Transaction::new();
+    transaction.stage_json(LOCAL_STATE_LABEL, chrome_state, &report.content)?;
+    if journal.record(LOCAL_STATE_LABEL, &report.changes) {
+        transaction.stage_json("Unpatch journal", &journal_path, &journal.to_json()?)?;
+    }
+    let committed = commit_workflow(transaction, &[])?;
+    Ok((report, Some(committed)))
+}
+
+/// What [`revert_journal`] put back.
+///
+/// # Examples
+///
+/// ```text
+/// RevertReport { restored: vec![("Local State".into(), 1)], transaction: Some(report) }
+/// ```
+#[derive(Debug, Default)]
+pub struct RevertReport {
+    /// Files and how many journaled fields each got back.
+    pub restored: Vec<(String, usize)>,
+    /// How the files were written; `None` if the journal was empty.
+    pub transaction: Option<TransactionReport>,
+}
+
+/// Put journaled fields of the restored files back to their original values.
+///
+/// `.bak` files only hold the state before the last run; the journal keeps
+/// the values from before the first one. MACs of reverted protected
+/// preferences are re-sealed as when patching. The journal is removed once
+/// it is empty.
+///
+/// # Examples
+///
+/// ```text
+/// let report = revert_journal(&chrome_state, &user_data_dir, None, &config.pref_hash)?;
+/// ```
+pub fn revert_journal(
+    chrome_state: &Path,
+    user_data_dir: &Path,
+    profile: Option<&str>,
+    pref_hash: &PrefHashConfig,
+) -> AppResult<RevertReport> {
+    let journal_path = user_data_dir.join(JOURNAL_FILE);
+    let mut journal = Journal::load(&journal_path)?;
+    if journal.entries.is_empty() {
+        return Ok(RevertReport::default());
+    }
+
+    let local_state = read_local_state(chrome_state)?;
+    let mut files = vec![(LOCAL_STATE_LABEL.to_string(), chrome_state.to_path_buf())];
+    for dir in target_profile_dirs(&local_state, profile)? {
+        if let Some(preferences) = preferences_path(user_data_dir, &dir) {
+            files.push((format!("{dir}/{PREFERENCES_FILE}"), preferences));
+        }
+    }
+
+    let mut transaction = Transaction::new();
+    let mut restored = Vec::new();
+    for (label, path) in files {
+        let entries = journal.take_file(&label);
+        if entries.is_empty() || !path.exists() {
+            continue;
+        }
+        let content = unpatch(&fs::read_to_string(&path)?, &entries)?;
+        if path == chrome_state {
+            transaction.stage_json(&label, &path, &content)?;
+        } else {
+            let pointers: Vec<&str> = entries.iter().map(|entry| entry.pointer.as_str()).collect();
+            stage_preferences(
+                &mut transaction,
+                &label,
+                &path,
+                &content,
+                &pointers,
+                pref_hash,
+            )?;
+        }
+        restored.push((label, entries.len()));
+    }
+    if !journal.entries.is_empty() {
+        transaction.stage_json("Unpatch journal", &journal_path, &journal.to_json()?)?;
+    }
+
+    let report = transaction.commit();
+    if report.is_success() && journal.entries.is_empty() {
+        fs::remove_file(&journal_path)?;
+    }
+    Ok(RevertReport {
+        restored,
+        transaction: Some(report),
+    })
+}
+
+/// Back up every staged Chrome file, then commit them all or roll back.
+///
+/// Files in `backed_up` keep the backup taken earlier in the run, so it
+/// still holds the state from before the run. The tool's own journal and
+/// patch state are not backed up: nothing restores them from a `.bak`.
+///
+/// # Examples
+///
+/// ```text
+/// let report = commit_workflow(transaction, &[])?.into_result()?;
+/// ```
+pub fn commit_workflow(
+    transaction: Transaction,
+    backed_up: &[PathBuf],
+) -> AppResult<TransactionReport> {
+    let own_file = |target: &Path| {
+        target
+            .file_name()
+            .is_some_and(|name| name == JOURNAL_FILE || name == STATE_FILE)
+    };
+    for target in transaction.targets().filter(|target| {
+        target.exists() && !own_file(target) && !backed_up.iter().any(|path| path == target)
+    }) {
+        let backup = backup_file(target)?;
+        log::info!("Backed up {} to {}", target.display(), backup.display());
+    }
+    log::info!("Writing {} file(s)...", transaction.len());
+    Ok(transaction.commit())
+}
+
+/// Stage edited Preferences, recomputing the MACs of `pointers` tracked in
+/// Preferences or Secure Preferences.
+///
+/// Returns how many MACs were recomputed; Secure Preferences is only staged
+/// when it tracks one of them. The hasher is only built when a MAC has to be
+/// recomputed, so a bad `pref_hash.seed` fails just the edits that need it.
+///
+/// # Examples
+///
+/// ```text
+/// let sealed = stage_preferences(&mut transaction, "Default/Preferences", &path, &content, &["/glic/launcher_enabled"], &config.pref_hash)?;
+/// ```
+pub fn stage_preferences(
+    transaction: &mut Transaction,
+    label: &str,
+    preferences: &Path,
+    content: &str,
+    pointers: &[&str],
+    pref_hash: &PrefHashConfig,
+) -> AppResult<usize> {
+    let mut prefs: Value = serde_json::from_str(content)
+        .map_err(|e| AppError::InvalidJson(format!("{label} parsing failed: {e}")))?;
+    let secure_path = preferences.with_file_name(SECURE_PREFERENCES_FILE);
+    let mut secure = read_json_if_exists(&secure_path)?;
+    let paths: Vec<String> = pointers.iter().map(|pointer| pref_path(pointer)).collect();
+    let tracked = |store: &Value| -> Vec<String> {
+        paths
+            .iter()
+            .filter(|path| is_tracked(store, path))
+            .cloned()
+            .collect()
+    };
+
+    let own = tracked(&prefs);
+    if !own.is_empty() {
+        update_macs(
+            &mut prefs,
+            &secure.iter().collect::<Vec<_>>(),
+            &own,
+            &pref_hash.hasher()?,
+        )?;
+    }
+    let content = serde_json::to_string(&prefs)
+        .map_err(|e| AppError::InvalidJson(format!("{label} serialization failed: {e}")))?;
+    transaction.stage_json(label, preferences, &content)?;
+
+    let mut sealed = own.len();
+    if let Some(secure) = secure.as_mut() {
+        let protected = tracked(secure);
+        if !protected.is_empty() {
+            update_macs(secure, &[&prefs], &protected, &pref_hash.hasher()?)?;
+            let content = serde_json::to_string(&*secure).map_err(|e| {
+                AppError::InvalidJson(format!(
+                    "{SECURE_PREFERENCES_FILE} serialization failed: {e}"
+                ))
+            })?;
+            let secure_label = format!(
+                "{}{SECURE_PREFERENCES_FILE}",
+                label.strip_suffix(PREFERENCES_FILE).unwrap_or(label)
+            );
+            transaction.stage_json(&secure_label, &secure_path, &content)?;
+            sealed += protected.len();
+        }
+    }
+    Ok(sealed)
+}
+
+/// Read Local State, failing with [`AppError::ConfigNotFound`] if it does not exist.
+///
+/// # Examples
+///
+/// ```text
+/// let content = read_local_state(&chrome_state)?;
+/// ```
+pub fn read_local_state(chrome_state: &Path) -> AppResult<String> {
+    match fs::read_to_string(chrome_state) {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Err(AppError::ConfigNotFound(chrome_state.to_path_buf()))
+        }
+        result => Ok(result?),
+    }
+}
+
+/// Read and parse a JSON file, or `None` if it does not exist.
+///
+/// # Examples
+///
+/// ```text
+/// let secure = read_json_if_exists(&profile_dir.join("Secure Preferences"))?;
+/// ```
+pub fn read_json_if_exists(path: &Path) -> AppResult<Option<Value>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let json = serde_json::from_str(&content)
+        .map_err(|e| AppError::InvalidJson(format!("{} parsing failed: {e}", path.display())))?;
+    Ok(Some(json))
+}
+
+/// Profile directories a profile-level operation applies to: the `--profile`
+/// selection, or every profile in Local State (`Default` if none are listed).
+///
+/// # Examples
+///
+/// ```
+/// use gemini_unlock::workflow::target_profile_dirs;
+///
+/// assert_eq!(target_profile_dirs("{}", None).unwrap(), ["Default"]);
+/// ```
+pub fn target_profile_dirs(local_state: &str, selector: Option<&str>) -> AppResult<Vec<String>> {
+    if let Some(selector) = selector {
+        return Ok(vec![selected_profile(local_state, selector)?.dir]);
+    }
+    let mut dirs: Vec<String> = list_profiles(local_state)?
+        .into_iter()
+        .map(|profile| profile.dir)
+        .collect();
+    if dirs.is_empty() {
+        dirs.push(DEFAULT_PROFILE_DIR.to_string());
+    }
+    Ok(dirs)
+}
+
+/// Path of a profile's Preferences, rejecting directory names that escape the user-data dir.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use gemini_unlock::workflow::preferences_path;
+///
+/// let dir = Path::new("/home/me/.config/google-chrome");
+/// assert_eq!(preferences_path(dir, "Default"), Some(dir.join("Default/Preferences")));
+/// assert_eq!(preferences_path(dir, "../x"), None);
+/// ```
+pub fn preferences_path(user_data_dir: &Path, dir: &str) -> Option<PathBuf> {
+    if Path::new(dir).components().count() != 1 || dir == ".." {
+        log::warn!("Skipping suspicious profile directory name: {dir}");
+        return None;
+    }
+    Some(user_data_dir.join(dir).join(PREFERENCES_FILE))
+}
+
+/// Resolve a `--profile` selector to a profile.
+///
+/// # Examples
+///
+/// ```text
+/// let profile = selected_profile(&local_state, "Work")?;
+/// ```
+pub fn selected_profile(local_state: &str, selector: &str) -> AppResult<ProfileInfo> {
+    let profiles = list_profiles(local_state)?;
+    Ok(select_profile(&profiles, selector)?.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::RULE_SETS;
+    use serde_json::json;
+
+    fn user_data_dir() -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().unwrap();
+        let local_state = json!({
+            "is_glic_eligible": false,
+            "variations_country": "cn",
+            "intl": {"app_locale": "de"},
+            "profile": {"info_cache": {"Default": {"name": "Person 1"}, "Profile 1": {"name": "Work"}}},
+        });
+        fs::write(dir.path().join(LOCAL_STATE_LABEL), local_state.to_string()).unwrap();
+        fs::create_dir(dir.path().join("Default")).unwrap();
+        fs::write(
+            dir.path().join("Default").join(PREFERENCES_FILE),
+            json!({"glic": {"launcher_enabled": false}}).to_string(),
+        )
+        .unwrap();
+        dir
+    }
+
+    fn options<'a>(pref_hash: &'a PrefHashConfig, dry_run: bool) -> ApplyOptions<'a> {
+        ApplyOptions {
+            chrome_version: None,
+            rule_set: &RULE_SETS[0],
+            profile: None,
+            locale: Some("en-US"),
+            accept_languages: false,
+            sync_safe_seed: false,
+            dry_run,
+            pref_hash,
+            backed_up: &[],
+        }
+    }
+
+    fn read(path: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn apply_writes_profiles_journal_and_state() {
+        let dir = user_data_dir();
+        let chrome_state = dir.path().join(LOCAL_STATE_LABEL);
+        let pref_hash = PrefHashConfig::default();
+        let report =
+            apply_workflow(&chrome_state, dir.path(), &options(&pref_hash, false)).unwrap();
+
+        assert!(report.transaction.unwrap().is_success());
+        assert_eq!(report.profiles.len(), 2);
+        assert_eq!(report.profiles[1].dir, "Profile 1");
+        assert_eq!(report.profiles[1].rules, None);
+        let local_state = read(&chrome_state);
+        assert_eq!(local_state["is_glic_eligible"], true);
+        assert_eq!(local_state["intl"]["app_locale"], "en-US");
+        let preferences = read(&dir.path().join("Default").join(PREFERENCES_FILE));
+        assert_eq!(preferences["glic"]["launcher_enabled"], true);
+        assert!(dir.path().join("Local State.bak").exists());
+        assert_eq!(
+            Journal::load(&dir.path().join(JOURNAL_FILE))
+                .unwrap()
+                .entries
+                .len(),
+            2
+        );
+        let state = PatchState::load(&dir.path().join(STATE_FILE))
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.locale.as_deref(), Some("en-US"));
+
+        apply_workflow(&chrome_state, dir.path(), &options(&pref_hash, false)).unwrap();
+        assert!(!dir.path().join(format!("{JOURNAL_FILE}.bak")).exists());
+        assert!(!dir.path().join(format!("{STATE_FILE}.bak")).exists());
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let dir = user_data_dir();
+        let chrome_state = dir.path().join(LOCAL_STATE_LABEL);
+        let before = fs::read_to_string(&chrome_state).unwrap();
+        let pref_hash = PrefHashConfig::default();
+        let report = apply_workflow(&chrome_state, dir.path(), &options(&pref_hash, true)).unwrap();
+
+        assert!(report.transaction.is_none());
+        assert!(report.local_state.unwrap().changed());
+        assert!(report.profiles[0].rules.as_ref().unwrap().changed());
+        assert_eq!(fs::read_to_string(&chrome_state).unwrap(), before);
+        assert!(!dir.path().join(JOURNAL_FILE).exists());
+        assert!(!dir.path().join(STATE_FILE).exists());
+        assert!(!dir.path().join("Local State.bak").exists());
+    }
+
+    #[test]
+    fn revert_restores_journaled_values() {
+        let dir = user_data_dir();
+        let chrome_state = dir.path().join(LOCAL_STATE_LABEL);
+        let pref_hash = PrefHashConfig::default();
+        apply_workflow(&chrome_state, dir.path(), &options(&pref_hash, false)).unwrap();
+        let report = revert_journal(&chrome_state, dir.path(), None, &pref_hash).unwrap();
+
+        assert!(report.transaction.unwrap().is_success());
+        assert_eq!(
+            report.restored,
+            [
+                (LOCAL_STATE_LABEL.to_string(), 1),
+                ("Default/Preferences".to_string(), 1)
+            ]
+        );
+        assert_eq!(read(&chrome_state)["intl"]["app_locale"], "de");
+        let preferences = read(&dir.path().join("Default").join(PREFERENCES_FILE));
+        assert_eq!(preferences["glic"]["launcher_enabled"], false);
+        assert!(!dir.path().join(JOURNAL_FILE).exists());
+
+        let report = revert_journal(&chrome_state, dir.path(), None, &pref_hash).unwrap();
+        assert!(report.restored.is_empty() && report.transaction.is_none());
+    }
+
+    #[test]
+    fn missing_local_state_is_reported() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pref_hash = PrefHashConfig::default();
+        let chrome_state = dir.path().join(LOCAL_STATE_LABEL);
+        assert!(matches!(
+            apply_workflow(&chrome_state, dir.path(), &options(&pref_hash, false)),
+            Err(AppError::ConfigNotFound(path)) if path == chrome_state
+        ));
+    }
+
+    #[test]
+    fn bad_seed_only_fails_protected_edits() {
+        let dir = user_data_dir();
+        let chrome_state = dir.path().join(LOCAL_STATE_LABEL);
+        let pref_hash = PrefHashConfig {
+            seed: "not hex".to_string(),
+            device_id: String::new(),
+        };
+        let mut options = options(&pref_hash, false);
+        options.locale = None;
+        apply_workflow(&chrome_state, dir.path(), &options).unwrap();
+
+        let secure = json!({"protection": {"macs": {"glic": {"launcher_enabled": "AB12"}}}});
+        let preferences = dir.path().join("Default").join(PREFERENCES_FILE);
+        fs::write(
+            preferences.with_file_name(SECURE_PREFERENCES_FILE),
+            secure.to_string(),
+        )
+        .unwrap();
+        fs::write(
+            &preferences,
+            json!({"glic": {"launcher_enabled": false}}).to_string(),
+        )
+        .unwrap();
+        assert!(matches!(
+            apply_workflow(&chrome_state, dir.path(), &options),
+            Err(AppError::InvalidSeed(_))
+        ));
+    }
+
+    #[test]
+    fn local_state_fix_leaves_profiles_alone() {
+        let dir = user_data_dir();
+        let chrome_state = dir.path().join(LOCAL_STATE_LABEL);
+        let preferences = dir.path().join("Default").join(PREFERENCES_FILE);
+        let before = fs::read_to_string(&preferences).unwrap();
+        let (report, transaction) =
+            patch_local_state(&chrome_state, dir.path(), &locale_rules("en-US")).unwrap();
+
+        assert!(report.changed() && transaction.unwrap().is_success());
+        let local_state = read(&chrome_state);
+        assert_eq!(local_state["intl"]["app_locale"], "en-US");
+        assert_eq!(local_state["is_glic_eligible"], false);
+        assert_eq!(fs::read_to_string(&preferences).unwrap(), before);
+        assert_eq!(
+            Journal::load(&dir.path().join(JOURNAL_FILE))
+                .unwrap()
+                .entries
+                .len(),
+            1
+        );
+        assert!(!dir.path().join(STATE_FILE).exists());
+
+        let (_, transaction) =
+            patch_local_state(&chrome_state, dir.path(), &locale_rules("en-US")).unwrap();
+        assert!(transaction.is_none());
+    }
+
+    #[test]
+    fn commit_keeps_earlier_backups() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("Preferences");
+        fs::write(&path, "{}").unwrap();
+        fs::write(dir.path().join("Preferences.bak"), "earlier").unwrap();
+        let mut transaction = Transaction::new();
+        transaction
+            .stage_json("Preferences", &path, "{\"a\":1}")
+            .unwrap();
+        let report = commit_workflow(transaction, std::slice::from_ref(&path)).unwrap();
+
+        assert!(report.is_success());
+        assert_eq!(read(&path), json!({"a": 1}));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("Preferences.bak")).unwrap(),
+            "earlier"
+        );
+    }
+}
@@ -2,14 +2,14 @@
 
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
 use gemini_unlock::patcher::apply_patches;
 use serde_json::json;
 
 /// Create test JSON configuration file
-fn create_test_config(dir: &PathBuf, name: &str) -> PathBuf {
+fn create_test_config(dir: &Path, name: &str) -> PathBuf {
     let config_path = dir.join(name);
     let content = r#"{
   "is_glic_eligible": false,
@@ -28,7 +28,7 @@ fn create_test_config(dir: &PathBuf, name: &str) -> PathBuf {
 fn test_complete_patch_workflow() {
     // Create temporary directory
     let temp_dir = TempDir::new().expect("Unable to create temporary directory");
-    let config_path = create_test_config(&temp_dir.path().to_path_buf(), "Local State");
+    let config_path = create_test_config(temp_dir.path(), "Local State");
 
     // Read original content
     let original_content = fs::read_to_string(&config_path).expect("Unable to read config file");
@@ -60,7 +60,7 @@ fn test_complete_patch_workflow() {
 #[test]
 fn test_patch_preserves_structure() {
     let temp_dir = TempDir::new().expect("Unable to create temporary directory");
-    let config_path = create_test_config(&temp_dir.path().to_path_buf(), "Local State");
+    let config_path = create_test_config(temp_dir.path(), "Local State");
 
     let content = fs::read_to_string(&config_path).expect("Unable to read config file");
     let report = apply_patches(&content).expect("Patch application failed");